@@ -1,7 +1,11 @@
 package pers.zhc.android.myapplication
 
 import android.view.Surface
+import org.json.JSONArray
 
+/**
+ * Native functions throw [RustException] if the Rust side fails or panics.
+ */
 object JNI {
     init {
         System.loadLibrary("app_jni")
@@ -10,15 +14,60 @@ object JNI {
     }
 
     private external fun initLogger()
-    external fun initWgpu(surface: Surface, animationId: Int): Long
+    /** @throws IllegalArgumentException if there's no animation called [animationName] */
+    external fun initWgpu(surface: Surface, animationName: String): Long
     external fun resize(addr: Long, width: Int, height: Int)
+    /** Releases the animation, its GPU device and its surface; [addr] is invalid afterward. */
     external fun cleanup(addr: Long)
-    external fun frame(addr: Long)
-    external fun changeAnimation(addr: Long, animationId: Int): Long
+    /**
+     * Releases the surface but keeps the animation, e.g. in `surfaceDestroyed`. Returns once
+     * the surface isn't used anymore.
+     */
+    external fun detachSurface(addr: Long)
+    /** Continues the animation on a new [surface] after [detachSurface]. */
+    external fun attachSurface(addr: Long, surface: Surface)
+    /**
+     * Asks the render thread for a frame at the vsync time [frameTimeNanos]
+     * (`System.nanoTime()` base, as from `Choreographer`).
+     *
+     * @throws RustException if the render thread has failed
+     */
+    external fun frame(addr: Long, frameTimeNanos: Long)
+    /** Stops rendering until [resume]; [frame] requests are dropped meanwhile. */
+    external fun pause(addr: Long)
+    external fun resume(addr: Long)
+    /** [onTouch] actions; keep in sync with `animation.rs`. */
+    const val TOUCH_DOWN = 0
+    const val TOUCH_MOVE = 1
+    const val TOUCH_UP = 2
+    const val TOUCH_PINCH = 3
+    const val TOUCH_DOUBLE_TAP = 4
 
-    enum class Animations(val id: Int) {
-        ROTATING_TRIANGLE(0),
-        VSBM(1),
+    /**
+     * Forwards a touch to the animation. [x] and [y] are in surface pixels; [scale] is the
+     * change of the finger distance for [TOUCH_PINCH] and ignored otherwise.
+     */
+    external fun onTouch(addr: Long, action: Int, pointerId: Int, x: Float, y: Float, scale: Float)
+    external fun gpuProfile(addr: Long): String
+    external fun frameStats(addr: Long): String
+    /**
+     * Switches the animation at [addr] to [animationName], keeping its GPU device and surface.
+     *
+     * @throws IllegalArgumentException if there's no animation called [animationName]
+     */
+    external fun changeAnimation(addr: Long, animationName: String)
+
+    /** JSON array of the scene registry (`wgpu_playground::registry::SCENES`). */
+    private external fun listAnimations(): String
+
+    data class Animation(val name: String, val description: String)
+
+    fun animations(): List<Animation> {
+        val array = JSONArray(listAnimations())
+        return (0 until array.length()).map {
+            val obj = array.getJSONObject(it)
+            Animation(obj.getString("name"), obj.getString("description"))
+        }
     }
 
     external fun simpleCompute(): String
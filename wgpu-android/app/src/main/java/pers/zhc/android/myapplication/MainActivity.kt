@@ -3,7 +3,11 @@ package pers.zhc.android.myapplication
 import android.content.Intent
 import android.os.Bundle
 import android.view.Choreographer
+import android.view.GestureDetector
+import android.view.MotionEvent
+import android.view.ScaleGestureDetector
 import android.view.SurfaceHolder
+import android.view.SurfaceView
 import android.widget.TextView
 import androidx.appcompat.app.AppCompatActivity
 import com.google.android.material.dialog.MaterialAlertDialogBuilder
@@ -11,14 +15,15 @@ import pers.zhc.android.myapplication.databinding.ActivityMainBinding
 
 class MainActivity : AppCompatActivity(), SurfaceHolder.Callback {
     private lateinit var tvFps: TextView
+    private lateinit var tvGpuProfile: TextView
     private lateinit var appendLog: (line: String) -> Unit
 
     // address of the underlying JNI object
     private var addr: Long = 0
 
-    private val defaultAnimation = JNI.Animations.ROTATING_TRIANGLE
+    private val defaultAnimation = "triangle-rotation"
 
-    private var lastFrameTimeNanos: Long = 0
+    private var lastStatsTimeNanos: Long = 0
 
     override fun onCreate(savedInstanceState: Bundle?) {
         super.onCreate(savedInstanceState)
@@ -30,61 +35,196 @@ class MainActivity : AppCompatActivity(), SurfaceHolder.Callback {
             }
         }
 
-        appendLog("simpleCompute result: ${JNI.simpleCompute()}")
+        try {
+            appendLog("simpleCompute result: ${JNI.simpleCompute()}")
+        } catch (e: RustException) {
+            appendLog("simpleCompute failed: ${e.message}")
+        }
 
         bindings.surfaceView.holder.addCallback(this)
+        setUpTouchInput(bindings.surfaceView)
 
         bindings.sha256MinerBtn.setOnClickListener {
             startActivity(Intent(this, Sha256MinerActivity::class.java))
         }
         tvFps = bindings.tvFps
+        tvGpuProfile = bindings.tvGpuProfile
         bindings.btnSelectAnimation.setOnClickListener {
-            val animations = JNI.Animations.entries.toTypedArray()
-            val items = animations.map { it.name }.toTypedArray()
+            val animations = JNI.animations()
+            val items = animations.map { "${it.name}\n${it.description}" }.toTypedArray()
 
             MaterialAlertDialogBuilder(this)
                 .setTitle("选择动画模式")
                 .setItems(items) { d, which ->
                     val selected = animations[which]
-                    val oldAddr = addr
-                    // set addr=0 to pause Choreographer's rendering
-                    addr = 0
-                    // then change animation
-                    val newAddr = JNI.changeAnimation(oldAddr, selected.id)
-                    addr = newAddr
+                    if (addr == 0L) return@setItems
+                    try {
+                        JNI.changeAnimation(addr, selected.name)
+                    } catch (e: RustException) {
+                        showError(e)
+                    } catch (e: IllegalArgumentException) {
+                        showError(e)
+                    }
                 }
                 .show()
         }
     }
 
-    override fun surfaceCreated(holder: SurfaceHolder) {
-        val surface = holder.surface
-        addr = JNI.initWgpu(surface, defaultAnimation.id)
-
-        Choreographer.getInstance().postFrameCallback(object : Choreographer.FrameCallback {
-            override fun doFrame(frameTimeNanos: Long) {
-                if (addr != 0L) {
-                    JNI.frame(addr)
-
-                    if (lastFrameTimeNanos != 0L) {
-                        val diffNanos = frameTimeNanos - lastFrameTimeNanos
-                        // 1s = 1,000,000,000ns
-                        val fps = 1_000_000_000.0 / diffNanos
-                        tvFps.text = String.format("FPS: %.1f", fps)
+    private fun setUpTouchInput(view: SurfaceView) {
+        val gestureDetector = GestureDetector(this, object : GestureDetector.SimpleOnGestureListener() {
+            override fun onDoubleTap(e: MotionEvent): Boolean {
+                sendTouch(JNI.TOUCH_DOUBLE_TAP, 0, e.x, e.y)
+                return true
+            }
+        })
+        val scaleDetector = ScaleGestureDetector(this, object : ScaleGestureDetector.SimpleOnScaleGestureListener() {
+            override fun onScale(detector: ScaleGestureDetector): Boolean {
+                sendTouch(JNI.TOUCH_PINCH, 0, detector.focusX, detector.focusY, detector.scaleFactor)
+                return true
+            }
+        })
+
+        view.setOnTouchListener { _, event ->
+            scaleDetector.onTouchEvent(event)
+            gestureDetector.onTouchEvent(event)
+            when (event.actionMasked) {
+                MotionEvent.ACTION_DOWN, MotionEvent.ACTION_POINTER_DOWN -> {
+                    val i = event.actionIndex
+                    sendTouch(JNI.TOUCH_DOWN, event.getPointerId(i), event.getX(i), event.getY(i))
+                }
+
+                MotionEvent.ACTION_UP, MotionEvent.ACTION_POINTER_UP -> {
+                    val i = event.actionIndex
+                    sendTouch(JNI.TOUCH_UP, event.getPointerId(i), event.getX(i), event.getY(i))
+                }
+
+                MotionEvent.ACTION_MOVE -> for (i in 0 until event.pointerCount) {
+                    sendTouch(JNI.TOUCH_MOVE, event.getPointerId(i), event.getX(i), event.getY(i))
+                }
+
+                MotionEvent.ACTION_CANCEL -> for (i in 0 until event.pointerCount) {
+                    sendTouch(JNI.TOUCH_UP, event.getPointerId(i), event.getX(i), event.getY(i))
+                }
+            }
+            true
+        }
+    }
+
+    private fun sendTouch(action: Int, pointerId: Int, x: Float, y: Float, scale: Float = 1f) {
+        if (addr == 0L) return
+        try {
+            JNI.onTouch(addr, action, pointerId, x, y, scale)
+        } catch (e: RustException) {
+            releaseAnimation()
+            showError(e)
+        }
+    }
+
+    private val frameCallback = object : Choreographer.FrameCallback {
+        override fun doFrame(frameTimeNanos: Long) {
+            if (addr != 0L) {
+                try {
+                    JNI.frame(addr, frameTimeNanos)
+
+                    // 1s = 1,000,000,000ns
+                    if (frameTimeNanos - lastStatsTimeNanos >= 1_000_000_000L) {
+                        tvFps.text = JNI.frameStats(addr)
+                        tvGpuProfile.text = JNI.gpuProfile(addr)
+                        lastStatsTimeNanos = frameTimeNanos
                     }
-                    lastFrameTimeNanos = frameTimeNanos
+                } catch (e: RustException) {
+                    // stop rendering instead of reporting the same error every frame
+                    releaseAnimation()
+                    showError(e)
                 }
-                Choreographer.getInstance().postFrameCallback(this)
             }
-        })
+            Choreographer.getInstance().postFrameCallback(this)
+        }
+    }
+
+    override fun onResume() {
+        super.onResume()
+        if (addr != 0L) {
+            try {
+                JNI.resume(addr)
+            } catch (e: RustException) {
+                releaseAnimation()
+                showError(e)
+            }
+        }
+        Choreographer.getInstance().postFrameCallback(frameCallback)
+    }
+
+    override fun onPause() {
+        super.onPause()
+        Choreographer.getInstance().removeFrameCallback(frameCallback)
+        if (addr != 0L) {
+            try {
+                JNI.pause(addr)
+            } catch (e: RustException) {
+                releaseAnimation()
+                showError(e)
+            }
+        }
+    }
+
+    override fun onDestroy() {
+        super.onDestroy()
+        releaseAnimation()
+    }
+
+    override fun surfaceCreated(holder: SurfaceHolder) {
+        try {
+            if (addr == 0L) {
+                addr = JNI.initWgpu(holder.surface, defaultAnimation)
+            } else {
+                // back from the background; the scene continues where it left off
+                JNI.attachSurface(addr, holder.surface)
+            }
+        } catch (e: RustException) {
+            showError(e)
+        } catch (e: IllegalArgumentException) {
+            // unknown animation name
+            showError(e)
+        }
     }
 
     override fun surfaceChanged(holder: SurfaceHolder, format: Int, width: Int, height: Int) {
-        JNI.resize(addr, width, height)
+        if (addr == 0L) return
+        try {
+            JNI.resize(addr, width, height)
+        } catch (e: RustException) {
+            showError(e)
+        }
     }
 
     override fun surfaceDestroyed(holder: SurfaceHolder) {
-        JNI.cleanup(addr)
+        if (addr == 0L) return
+        try {
+            // the surface must not be used after this returns
+            JNI.detachSurface(addr)
+        } catch (e: RustException) {
+            releaseAnimation()
+            showError(e)
+        }
+    }
+
+    private fun releaseAnimation() {
+        if (addr == 0L) return
+        val oldAddr = addr
         addr = 0
+        try {
+            JNI.cleanup(oldAddr)
+        } catch (e: RustException) {
+            showError(e)
+        }
+    }
+
+    private fun showError(e: RuntimeException) {
+        MaterialAlertDialogBuilder(this)
+            .setTitle("Rust 错误")
+            .setMessage(e.message)
+            .setPositiveButton("确定", null)
+            .show()
     }
 }
@@ -0,0 +1,8 @@
+package pers.zhc.android.myapplication
+
+/**
+ * Thrown by [JNI] functions when the Rust side returns an error or panics.
+ *
+ * The message holds the error chain and backtrace.
+ */
+class RustException(message: String) : RuntimeException(message)
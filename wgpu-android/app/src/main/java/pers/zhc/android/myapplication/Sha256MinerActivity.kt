@@ -3,6 +3,7 @@ package pers.zhc.android.myapplication
 import android.os.Bundle
 import android.widget.ScrollView
 import androidx.appcompat.app.AppCompatActivity
+import com.google.android.material.dialog.MaterialAlertDialogBuilder
 import pers.zhc.android.myapplication.databinding.Sha256MinerActivityMainBinding
 class Sha256MinerActivity : AppCompatActivity() {
     override fun onCreate(savedInstanceState: Bundle?) {
@@ -27,20 +28,30 @@ class Sha256MinerActivity : AppCompatActivity() {
             appendLog("开始计算……")
             bindings.btnStartMining.isEnabled = false
             Thread {
-                JNI.sha256Demo(
-                    bindings.etWorkgroupSize.text!!.toString().toInt(),
-                    bindings.etDispatchX.text!!.toString().toInt(),
-                    bindings.etIterations.text!!.toString().toInt(),
-                    bindings.etDifficulty.text!!.toString().toInt(),
-                    object : JNI.LogCallback() {
-                        override fun print(line: String) {
-                            runOnUiThread {
-                                appendLog(line)
-                                appendLog("")
+                try {
+                    JNI.sha256Demo(
+                        bindings.etWorkgroupSize.text!!.toString().toInt(),
+                        bindings.etDispatchX.text!!.toString().toInt(),
+                        bindings.etIterations.text!!.toString().toInt(),
+                        bindings.etDifficulty.text!!.toString().toInt(),
+                        object : JNI.LogCallback() {
+                            override fun print(line: String) {
+                                runOnUiThread {
+                                    appendLog(line)
+                                    appendLog("")
+                                }
                             }
                         }
+                    )
+                } catch (e: RustException) {
+                    runOnUiThread {
+                        MaterialAlertDialogBuilder(this@Sha256MinerActivity)
+                            .setTitle("Rust 错误")
+                            .setMessage(e.message)
+                            .setPositiveButton("确定", null)
+                            .show()
                     }
-                )
+                }
                 runOnUiThread {
                     bindings.btnStartMining.isEnabled = true
                     appendLog("----------------")
@@ -1,171 +1,277 @@
 use wgpu::util::RenderEncoder;
 
 pub mod jni_exports {
-    use crate::animator::{Animate, RotatingTriangleAnimator, VsbmAnimator};
-    use crate::{animator, default, AndroidWindow};
-    use jni::objects::{JClass, JObject};
-    use jni::sys::{jfloat, jint, jlong};
+    use crate::handle::HandleRegistry;
+    use crate::jni_guard::guard;
+    use crate::render_thread::{Command, RenderThread};
+    use crate::AndroidWindow;
+    use anyhow::bail;
+    use jni::objects::{JClass, JObject, JString};
+    use jni::sys::{jfloat, jint, jlong, jstring};
     use jni::JNIEnv;
-    use log::{debug, error, info, trace};
-    use std::sync::{Arc, Mutex};
-    use wgpu::{BackendOptions, Backends, Instance, InstanceDescriptor};
-    use wgpu_playground::WgpuStateInitInfo;
-
-    struct Wrapper {
-        animator: Box<dyn Animate>,
-        window: Arc<AndroidWindow>,
-    }
-
-    fn create_init_info_from_window(android_window: Arc<AndroidWindow>) -> WgpuStateInitInfo {
-        let instance = Instance::new(&InstanceDescriptor {
-            backends: Backends::VULKAN,
-            ..default!()
-        });
-        let size = android_window.size;
-        let surface = instance
-            .create_surface(Arc::clone(&android_window))
-            .unwrap();
-
-        let init_info = WgpuStateInitInfo {
-            instance,
-            surface,
-            size,
-        };
-        init_info
+    use log::{error, info};
+    use std::ptr::null_mut;
+    use wgpu_playground::registry::{find_scene, SceneEntry, SCENES};
+    use wgpu_playground::scene::InputEvent;
+
+    static RENDER_THREADS: HandleRegistry<RenderThread> = HandleRegistry::new();
+
+    /// Sends `command` to the render thread behind `addr`.
+    fn send(addr: jlong, command: Command) -> anyhow::Result<()> {
+        RENDER_THREADS.get(addr)?.send(command)
     }
 
+    /// Starts rendering `animation_name` to `surface` on a new render thread and returns its
+    /// handle.
     #[unsafe(no_mangle)]
     #[allow(non_snake_case)]
     pub extern "system" fn Java_pers_zhc_android_myapplication_JNI_initWgpu(
-        env: JNIEnv,
+        mut env: JNIEnv,
         _c: JClass,
         surface: JObject,
-        animation_id: jint,
+        animation_name: JString,
     ) -> jlong {
         info!("initWgpu called");
 
-        unsafe {
-            let window_ptr =
-                ndk_sys::ANativeWindow_fromSurface(env.get_native_interface(), surface.as_raw());
-            let width = ndk_sys::ANativeWindow_getWidth(window_ptr);
-            let height = ndk_sys::ANativeWindow_getHeight(window_ptr);
-            // let size = (width as u32, height as u32);
-            let size = (1024, 1024);
-            info!("Surface size: {:?}", size);
-
-            if window_ptr.is_null() {
-                error!("window_ptr is null");
-                return 0;
-            }
-
-            let android_window = AndroidWindow {
-                native_window: window_ptr,
-                size,
+        guard(&mut env, 0, |env| {
+            let Some(entry) = scene_entry(env, &animation_name)? else {
+                return Ok(0);
             };
-            let android_window = Arc::new(android_window);
-
-            let result = pollster::block_on(async {
-                let result: anyhow::Result<jlong> = try {
-                    let init_info = create_init_info_from_window(Arc::clone(&android_window));
-                    let animator = create_animator(animation_id, init_info)?;
-
-                    let wrapper = Wrapper {
-                        animator,
-                        window: android_window,
-                    };
-                    Box::into_raw(Box::new(wrapper)) as jlong
-                };
-                result
-            });
-            result.unwrap_or_else(|e| {
-                error!("JNI error: {:?}", e);
-                0
-            })
+            let android_window = android_window(env, &surface)?;
+            let render_thread = RenderThread::spawn(android_window, entry)?;
+            Ok(RENDER_THREADS.insert(render_thread))
+        })
+    }
+
+    /// Acquires the native window of the `android.view.Surface` `surface`.
+    fn android_window(env: &JNIEnv, surface: &JObject) -> anyhow::Result<AndroidWindow> {
+        let window_ptr = unsafe {
+            ndk_sys::ANativeWindow_fromSurface(env.get_native_interface(), surface.as_raw())
+        };
+        if window_ptr.is_null() {
+            bail!("ANativeWindow_fromSurface returned null");
         }
+        let window = AndroidWindow {
+            native_window: window_ptr,
+        };
+        info!("Surface size: {:?}", window.size());
+        Ok(window)
     }
 
-    fn create_animator(
-        animation_id: jint,
-        init_info: WgpuStateInitInfo,
-    ) -> anyhow::Result<Box<dyn Animate>> {
-        let animator: Box<dyn Animate> = match animation_id {
-            0 => Box::new(RotatingTriangleAnimator::new(init_info)?),
-            1 => Box::new(VsbmAnimator::new(init_info)?),
-            _ => {
-                error!("Unknown animation id");
-                panic!();
+    /// Looks up a scene of the registry by name. Throws `IllegalArgumentException` and
+    /// returns `None` if there's no such scene; fails if `name` can't be read.
+    fn scene_entry(
+        env: &mut JNIEnv,
+        name: &JString,
+    ) -> anyhow::Result<Option<&'static SceneEntry>> {
+        let name: String = env.get_string(name)?.into();
+        Ok(match find_scene(&name) {
+            Some(i) => Some(&SCENES[i]),
+            None => {
+                error!("Unknown animation: {}", name);
+                let _ = env.throw_new(
+                    "java/lang/IllegalArgumentException",
+                    format!("Unknown animation: {}", name),
+                );
+                None
             }
-        };
-        Ok(animator)
+        })
+    }
+
+    /// All scenes as a JSON array of `{"name", "description", "defaults"}` objects.
+    #[unsafe(no_mangle)]
+    #[allow(non_snake_case)]
+    pub extern "system" fn Java_pers_zhc_android_myapplication_JNI_listAnimations(
+        mut env: JNIEnv,
+        _c: JClass,
+    ) -> jstring {
+        guard(&mut env, null_mut(), |env| {
+            let json = serde_json::to_string(SCENES)?;
+            Ok(env.new_string(json)?.into_raw())
+        })
     }
 
     #[unsafe(no_mangle)]
     #[allow(non_snake_case)]
     pub extern "system" fn Java_pers_zhc_android_myapplication_JNI_resize(
-        _env: JNIEnv,
+        mut env: JNIEnv,
         _c: JClass,
         addr: jlong,
         width: jint,
         height: jint,
     ) {
-        info!("resize called");
-        let wrapper = unsafe { &mut *(addr as *mut Wrapper) };
-        // wrapper.animator.resize((width as _, height as _)).unwrap();
+        info!("resize called: {}x{}", width, height);
+        guard(&mut env, (), |_| {
+            send(addr, Command::Resize((width as _, height as _)))
+        })
     }
 
+    /// Stops the render thread and releases the device, the scene and the window.
     #[unsafe(no_mangle)]
     #[allow(non_snake_case)]
     pub extern "system" fn Java_pers_zhc_android_myapplication_JNI_cleanup(
-        _env: JNIEnv,
+        mut env: JNIEnv,
         _c: JClass,
         addr: jlong,
     ) {
         info!("cleanup called");
-        unsafe {
-            let wrapper = Box::from_raw(addr as *mut Wrapper);
-            let animator = wrapper.animator;
-            drop(animator);
-            debug!("strong: {}", Arc::strong_count(&wrapper.window));
-            let Ok(w) = Arc::try_unwrap(wrapper.window) else {
-                panic!("Should have only one reference here");
-            };
-            ndk_sys::ANativeWindow_release(w.native_window);
-        }
+        guard(&mut env, (), |_| {
+            drop(RENDER_THREADS.remove(addr)?);
+            Ok(())
+        })
     }
 
+    /// Requests a frame for the vsync at `frame_time_nanos`. Throws if the render thread has
+    /// failed.
     #[unsafe(no_mangle)]
     #[allow(non_snake_case)]
     pub extern "system" fn Java_pers_zhc_android_myapplication_JNI_frame(
-        env: JNIEnv,
+        mut env: JNIEnv,
+        _c: JClass,
+        addr: jlong,
+        frame_time_nanos: jlong,
+    ) {
+        guard(&mut env, (), |_| {
+            send(
+                addr,
+                Command::Frame {
+                    vsync_nanos: frame_time_nanos,
+                },
+            )
+        })
+    }
+
+    #[unsafe(no_mangle)]
+    #[allow(non_snake_case)]
+    pub extern "system" fn Java_pers_zhc_android_myapplication_JNI_pause(
+        mut env: JNIEnv,
+        _c: JClass,
+        addr: jlong,
+    ) {
+        info!("pause called");
+        guard(&mut env, (), |_| send(addr, Command::Pause))
+    }
+
+    #[unsafe(no_mangle)]
+    #[allow(non_snake_case)]
+    pub extern "system" fn Java_pers_zhc_android_myapplication_JNI_resume(
+        mut env: JNIEnv,
         _c: JClass,
         addr: jlong,
     ) {
-        trace!("update called");
-        let wrapper = unsafe { &mut *(addr as *mut Wrapper) };
-        wrapper.animator.frame().unwrap();
+        info!("resume called");
+        guard(&mut env, (), |_| send(addr, Command::Resume))
     }
 
+    /// Stops rendering to the current surface and releases it, keeping the scene. Call from
+    /// `surfaceDestroyed`; returns once the surface isn't used anymore.
+    #[unsafe(no_mangle)]
+    #[allow(non_snake_case)]
+    pub extern "system" fn Java_pers_zhc_android_myapplication_JNI_detachSurface(
+        mut env: JNIEnv,
+        _c: JClass,
+        addr: jlong,
+    ) {
+        info!("detachSurface called");
+        guard(&mut env, (), |_| {
+            let render_thread = RENDER_THREADS.get(addr)?;
+            render_thread.detach_window()
+        })
+    }
+
+    /// Continues the animation on a new surface.
+    #[unsafe(no_mangle)]
+    #[allow(non_snake_case)]
+    pub extern "system" fn Java_pers_zhc_android_myapplication_JNI_attachSurface(
+        mut env: JNIEnv,
+        _c: JClass,
+        addr: jlong,
+        surface: JObject,
+    ) {
+        info!("attachSurface called");
+        guard(&mut env, (), |env| {
+            let render_thread = RENDER_THREADS.get(addr)?;
+            render_thread.attach_window(android_window(env, &surface)?)
+        })
+    }
+
+    /// Action codes of `onTouch`, as in `JNI.kt`.
+    const TOUCH_DOWN: jint = 0;
+    const TOUCH_MOVE: jint = 1;
+    const TOUCH_UP: jint = 2;
+    const TOUCH_PINCH: jint = 3;
+    const TOUCH_DOUBLE_TAP: jint = 4;
+
+    /// Forwards a touch to the scene. `x` and `y` are in surface pixels; `scale` is only used
+    /// by pinches.
+    #[unsafe(no_mangle)]
+    #[allow(non_snake_case)]
+    pub extern "system" fn Java_pers_zhc_android_myapplication_JNI_onTouch(
+        mut env: JNIEnv,
+        _c: JClass,
+        addr: jlong,
+        action: jint,
+        pointer_id: jint,
+        x: jfloat,
+        y: jfloat,
+        scale: jfloat,
+    ) {
+        guard(&mut env, (), |_| {
+            let id = pointer_id as u32;
+            let event = match action {
+                TOUCH_DOWN => InputEvent::PointerPressed { id, x, y },
+                TOUCH_MOVE => InputEvent::PointerMoved { id, x, y },
+                TOUCH_UP => InputEvent::PointerReleased { id, x, y },
+                TOUCH_PINCH => InputEvent::Pinch { scale },
+                TOUCH_DOUBLE_TAP => InputEvent::DoubleTap { x, y },
+                _ => bail!("Unknown touch action: {}", action),
+            };
+            send(addr, Command::Input(event))
+        })
+    }
+
+    #[unsafe(no_mangle)]
+    #[allow(non_snake_case)]
+    pub extern "system" fn Java_pers_zhc_android_myapplication_JNI_frameStats(
+        mut env: JNIEnv,
+        _c: JClass,
+        addr: jlong,
+    ) -> jstring {
+        guard(&mut env, null_mut(), |env| {
+            let render_thread = RENDER_THREADS.get(addr)?;
+            Ok(env.new_string(render_thread.frame_stats())?.into_raw())
+        })
+    }
+
+    #[unsafe(no_mangle)]
+    #[allow(non_snake_case)]
+    pub extern "system" fn Java_pers_zhc_android_myapplication_JNI_gpuProfile(
+        mut env: JNIEnv,
+        _c: JClass,
+        addr: jlong,
+    ) -> jstring {
+        guard(&mut env, null_mut(), |env| {
+            let render_thread = RENDER_THREADS.get(addr)?;
+            Ok(env.new_string(render_thread.gpu_profile())?.into_raw())
+        })
+    }
+
+    /// Switches the animation at `addr` to another scene, reusing its device and surface.
     #[unsafe(no_mangle)]
     #[allow(non_snake_case)]
     pub extern "system" fn Java_pers_zhc_android_myapplication_JNI_changeAnimation(
-        env: JNIEnv,
+        mut env: JNIEnv,
         _c: JClass,
         addr: jlong,
-        animation_id: jint,
-    ) -> jlong {
+        animation_name: JString,
+    ) {
         info!("changeAnimation called");
-        let wrapper = unsafe { Box::from_raw(addr as *mut Wrapper) };
-        // destroy the old animator and take android_window out
-        drop(wrapper.animator);
-        let window_arc = wrapper.window;
-        let init_info = create_init_info_from_window(Arc::clone(&window_arc));
-
-        let animator = create_animator(animation_id, init_info).unwrap();
-        let wrapper = Wrapper {
-            window: window_arc,
-            animator,
-        };
-        info!("changeAnimation end");
-        Box::into_raw(Box::new(wrapper)) as jlong
+        guard(&mut env, (), |env| {
+            // keep the current animation if the new one doesn't exist
+            let Some(entry) = scene_entry(env, &animation_name)? else {
+                return Ok(());
+            };
+            send(addr, Command::ChangeScene(entry))
+        })
     }
 }
@@ -1,59 +1,81 @@
 pub trait Animate {
-    fn new(init_info: WgpuStateInitInfo) -> anyhow::Result<Self>
-    where
-        Self: Sized;
-
-    fn frame(&mut self) -> anyhow::Result<()>;
+    /// Renders a frame and returns whether it was presented.
+    fn frame(&mut self) -> anyhow::Result<bool>;
 
     fn resize(&mut self, new_size: (u32, u32)) -> anyhow::Result<()>;
+
+    /// Rolling per-pass GPU timings.
+    fn gpu_profile(&mut self) -> String;
+
+    /// Switches to another scene on the same device and surface.
+    fn change_scene(&mut self, entry: &'static SceneEntry);
+
+    /// Doesn't animate the time until the next frame, e.g. while rendering was paused.
+    fn reset_clock(&mut self);
+
+    /// Stops presenting to the current window, keeping the device and the scene.
+    fn detach_window(&mut self);
+
+    /// Presents to `window` from now on.
+    fn attach_window(&mut self, window: Arc<AndroidWindow>) -> anyhow::Result<()>;
+
+    fn input(&mut self, event: &InputEvent);
 }
 
-use log::info;
-use wgpu_playground::{triangle_rotation, vsbm, WgpuStateInitInfo};
+use crate::AndroidWindow;
+use std::sync::Arc;
+use wgpu_playground::registry::{SceneEntry, SceneOptions};
+use wgpu_playground::scene::{InputEvent, Scene, SceneHost};
+use wgpu_playground::WgpuStateInitInfo;
 
-pub struct RotatingTriangleAnimator {
-    state: triangle_rotation::State,
-    elapsed: f32,
+/// Shows a scene of the shared registry with its default options.
+pub struct SceneAnimator {
+    host: SceneHost<Box<dyn Scene>>,
 }
 
-impl Animate for RotatingTriangleAnimator {
-    fn new(init_info: WgpuStateInitInfo) -> anyhow::Result<Self> {
-        Ok(Self {
-            state: pollster::block_on(triangle_rotation::State::new(init_info)),
-            elapsed: 0f32,
-        })
+impl SceneAnimator {
+    pub fn new(init_info: WgpuStateInitInfo, entry: &'static SceneEntry) -> anyhow::Result<Self> {
+        let host = pollster::block_on(SceneHost::new(init_info, |gpu, format, size| {
+            entry.create(gpu, format, size, &SceneOptions::default())
+        }))?;
+        Ok(Self { host })
     }
+}
 
-    fn frame(&mut self) -> anyhow::Result<()> {
-        self.elapsed += 0.005;
-        self.state.update_elapsed(self.elapsed);
-        self.state.render(|| {});
-        Ok(())
+impl Animate for SceneAnimator {
+    fn frame(&mut self) -> anyhow::Result<bool> {
+        Ok(self.host.frame(|| {})?)
     }
 
     fn resize(&mut self, new_size: (u32, u32)) -> anyhow::Result<()> {
-        Ok(self.state.resize(new_size))
+        Ok(self.host.resize(new_size))
     }
-}
 
-pub struct VsbmAnimator {
-    state: vsbm::State,
-}
+    fn gpu_profile(&mut self) -> String {
+        self.host.profiler().summary()
+    }
 
-impl Animate for VsbmAnimator {
-    fn new(init_info: WgpuStateInitInfo) -> anyhow::Result<Self> {
-        Ok(Self {
-            state: pollster::block_on(vsbm::State::new(init_info)),
-        })
+    fn change_scene(&mut self, entry: &'static SceneEntry) {
+        self.host.replace_scene(|gpu, format, size| {
+            entry.create(gpu, format, size, &SceneOptions::default())
+        });
     }
 
-    fn frame(&mut self) -> anyhow::Result<()> {
-        self.state.update();
-        self.state.render(|| {})?;
-        Ok(())
+    fn reset_clock(&mut self) {
+        self.host.reset_clock();
     }
 
-    fn resize(&mut self, new_size: (u32, u32)) -> anyhow::Result<()> {
-        Ok(self.state.resize(new_size))
+    fn detach_window(&mut self) {
+        self.host.detach_surface();
+    }
+
+    fn attach_window(&mut self, window: Arc<AndroidWindow>) -> anyhow::Result<()> {
+        let size = window.size();
+        let surface = self.host.gpu().instance.create_surface(window)?;
+        self.host.attach_surface(surface, size)
+    }
+
+    fn input(&mut self, event: &InputEvent) {
+        self.host.input(event);
     }
 }
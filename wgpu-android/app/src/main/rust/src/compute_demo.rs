@@ -125,16 +125,19 @@ pub mod jni_exports {
     use jni::JNIEnv;
     use jni::objects::JClass;
     use jni::sys::jstring;
+    use std::ptr::null_mut;
     use crate::compute_demo;
+    use crate::jni_guard::guard;
 
     #[unsafe(no_mangle)]
     #[allow(non_snake_case)]
     pub extern "system" fn Java_pers_zhc_android_myapplication_JNI_simpleCompute(
-        env: JNIEnv,
+        mut env: JNIEnv,
         _c: JClass,
     ) -> jstring {
-        let result = compute_demo::compute();
-        let result = pollster::block_on(result).unwrap();
-        env.new_string(format!("{:?}", result)).unwrap().into_raw()
+        guard(&mut env, null_mut(), |env| {
+            let result = pollster::block_on(compute_demo::compute())?;
+            Ok(env.new_string(format!("{:?}", result))?.into_raw())
+        })
     }
 }
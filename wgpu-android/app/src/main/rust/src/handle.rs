@@ -0,0 +1,68 @@
+//! Rust objects owned by Java as `jlong` handles.
+//!
+//! Java only gets ids; the objects stay in a [`HandleRegistry`], so a stale or already released
+//! handle is an error instead of a dangling pointer. Objects are shared as [`Arc`]s, so they
+//! must synchronize their own mutation.
+
+use anyhow::anyhow;
+use jni::sys::jlong;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+struct Entries<T> {
+    /// Ids aren't reused, so a released handle can't reach a newer object
+    next_id: jlong,
+    objects: BTreeMap<jlong, Arc<T>>,
+}
+
+/// Objects of type `T` that Java holds by id.
+pub struct HandleRegistry<T> {
+    entries: Mutex<Entries<T>>,
+}
+
+impl<T> HandleRegistry<T> {
+    pub const fn new() -> Self {
+        Self {
+            entries: Mutex::new(Entries {
+                // 0 stays free for "no object"
+                next_id: 1,
+                objects: BTreeMap::new(),
+            }),
+        }
+    }
+
+    /// Stores `value` and returns its handle for Java to keep.
+    pub fn insert(&self, value: T) -> jlong {
+        let mut entries = self.entries.lock().unwrap();
+        let id = entries.next_id;
+        entries.next_id += 1;
+        entries.objects.insert(id, Arc::new(value));
+        id
+    }
+
+    /// The object behind `handle`.
+    pub fn get(&self, handle: jlong) -> anyhow::Result<Arc<T>> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .objects
+            .get(&handle)
+            .cloned()
+            .ok_or_else(|| anyhow!("Invalid handle: {}", handle))
+    }
+
+    /// Takes the object behind `handle` out of the registry. It's dropped once the returned
+    /// `Arc` and those still held from [`Self::get`] are.
+    pub fn remove(&self, handle: jlong) -> anyhow::Result<Arc<T>> {
+        let mut entries = self.entries.lock().unwrap();
+        entries
+            .objects
+            .remove(&handle)
+            .ok_or_else(|| anyhow!("Invalid handle: {}", handle))
+    }
+}
+
+impl<T> Default for HandleRegistry<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,69 @@
+//! Keeps panics and errors from crossing the JNI boundary.
+//!
+//! Unwinding out of an `extern "system"` function aborts the process, so every export runs its
+//! body through [`guard`], which turns failures into a thrown `RustException` instead.
+
+use jni::JNIEnv;
+use log::error;
+use std::any::Any;
+use std::cell::RefCell;
+use std::panic::{self, AssertUnwindSafe};
+
+/// `pers.zhc.android.myapplication.RustException`
+const EXCEPTION_CLASS: &str = "pers/zhc/android/myapplication/RustException";
+
+thread_local! {
+    /// Backtrace of the last panic on this thread, captured by the panic hook while the
+    /// panicking frames are still on the stack
+    static PANIC_BACKTRACE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Called from the panic hook.
+pub(crate) fn set_panic_backtrace(backtrace: String) {
+    PANIC_BACKTRACE.with(|x| *x.borrow_mut() = Some(backtrace));
+}
+
+/// Runs `f` and returns its result, or throws a `RustException` and returns `sentinel` if it
+/// fails or panics.
+///
+/// An exception already pending in `env` (e.g. thrown by `f` itself) is left as is.
+pub fn guard<R>(
+    env: &mut JNIEnv,
+    sentinel: R,
+    f: impl FnOnce(&mut JNIEnv) -> anyhow::Result<R>,
+) -> R {
+    let message = match panic::catch_unwind(AssertUnwindSafe(|| f(env))) {
+        Ok(Ok(r)) => return r,
+        // includes the causes and, with RUST_BACKTRACE set, anyhow's backtrace
+        Ok(Err(e)) => format!("{:?}", e),
+        Err(payload) => panic_report(&*payload),
+    };
+    error!("JNI error: {}", message);
+
+    if !env.exception_check().unwrap_or(false) {
+        if let Err(e) = env.throw_new(EXCEPTION_CLASS, message) {
+            error!("Failed to throw {}: {:?}", EXCEPTION_CLASS, e);
+        }
+    }
+    sentinel
+}
+
+/// Message and backtrace of a panic caught on the current thread.
+pub(crate) fn panic_report(payload: &(dyn Any + Send)) -> String {
+    let backtrace = PANIC_BACKTRACE.with(|x| x.borrow_mut().take());
+    format!(
+        "Rust panic: {}\n\nBacktrace:\n{}",
+        panic_message(payload),
+        backtrace.as_deref().unwrap_or("<unavailable>")
+    )
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s
+    } else {
+        "<non-string payload>"
+    }
+}
@@ -1,7 +1,9 @@
 #![feature(decl_macro)]
-#![feature(try_blocks)]
 
 mod compute_demo;
+mod handle;
+mod jni_guard;
+mod render_thread;
 pub mod animation;
 pub mod sha256_miner;
 pub mod animator;
@@ -42,7 +44,18 @@ pub extern "system" fn Java_pers_zhc_android_myapplication_JNI_initLogger(
 
 pub struct AndroidWindow {
     native_window: *mut ndk_sys::ANativeWindow,
-    size: (u32, u32),
+}
+
+impl AndroidWindow {
+    /// Current size of the window's buffers, which changes on rotation.
+    pub fn size(&self) -> (u32, u32) {
+        unsafe {
+            let width = ndk_sys::ANativeWindow_getWidth(self.native_window);
+            let height = ndk_sys::ANativeWindow_getHeight(self.native_window);
+            // negative on error
+            (width.max(0) as u32, height.max(0) as u32)
+        }
+    }
 }
 
 impl HasWindowHandle for AndroidWindow {
@@ -64,6 +77,17 @@ impl HasDisplayHandle for AndroidWindow {
     }
 }
 
+/// Releases the reference taken by `ANativeWindow_fromSurface`, once the last `Arc` (ours or the
+/// wgpu surface's) is gone.
+impl Drop for AndroidWindow {
+    fn drop(&mut self) {
+        debug!("Releasing native window");
+        unsafe {
+            ndk_sys::ANativeWindow_release(self.native_window);
+        }
+    }
+}
+
 unsafe impl Send for AndroidWindow {}
 unsafe impl Sync for AndroidWindow {}
 
@@ -75,5 +99,7 @@ fn set_up_panic_hook() {
         let backtrace = Backtrace::new();
         let backtrace = format!("{:?}", backtrace);
         error!("Rust panic!!\n{}\nBacktrace:\n{}", i, backtrace);
+        // the panic is rethrown to Java by `jni_guard::guard`
+        jni_guard::set_panic_backtrace(backtrace);
     }));
 }
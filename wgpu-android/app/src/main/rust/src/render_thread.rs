@@ -0,0 +1,254 @@
+//! The thread an animation renders on.
+//!
+//! Java only sends [`Command`]s; the animator, its device and the window never leave the
+//! render thread, so calls from the UI thread can't race with a frame in flight. The thread,
+//! and the scene with it, outlives the window: it's detached when the app goes to the
+//! background and a new one attached when it comes back.
+
+use crate::animator::{Animate, SceneAnimator};
+use crate::jni_guard::panic_report;
+use crate::{default, AndroidWindow};
+use anyhow::anyhow;
+use log::{debug, error, info, trace};
+use std::iter;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use wgpu::{Backends, Instance, InstanceDescriptor};
+use wgpu_playground::frame_stats::FrameStats;
+use wgpu_playground::registry::SceneEntry;
+use wgpu_playground::scene::InputEvent;
+use wgpu_playground::WgpuStateInitInfo;
+
+pub enum Command {
+    /// Render a frame for the vsync at this `System.nanoTime()`
+    Frame {
+        vsync_nanos: i64,
+    },
+    /// Stop rendering until [`Command::Resume`]
+    Pause,
+    Resume,
+    Resize((u32, u32)),
+    ChangeScene(&'static SceneEntry),
+    Input(InputEvent),
+    /// Drop the surface and the window, and reply once they're gone
+    DetachWindow(oneshot::Sender<()>),
+    AttachWindow(AndroidWindow, oneshot::Sender<anyhow::Result<()>>),
+    Shutdown,
+}
+
+/// What the render thread publishes for the UI.
+#[derive(Default)]
+struct Shared {
+    frame_stats: String,
+    gpu_profile: String,
+    /// Set when the render thread has failed and exited
+    error: Option<String>,
+}
+
+pub struct RenderThread {
+    commands: Sender<Command>,
+    shared: Arc<Mutex<Shared>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl RenderThread {
+    /// Starts rendering `entry` to `window`. Returns once the scene has been created.
+    pub fn spawn(window: AndroidWindow, entry: &'static SceneEntry) -> anyhow::Result<Self> {
+        let (commands, receiver) = mpsc::channel();
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        let (init_tx, init_rx) = oneshot::channel();
+
+        let thread = thread::Builder::new().name("render".into()).spawn({
+            let shared = Arc::clone(&shared);
+            move || {
+                let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                    let window = Arc::new(window);
+                    let animator = match create_animator(&window, entry) {
+                        Ok(x) => x,
+                        Err(e) => {
+                            let _ = init_tx.send(Err(e));
+                            return Ok(());
+                        }
+                    };
+                    let _ = init_tx.send(Ok(()));
+                    render_loop(animator, window, receiver, &shared)
+                }));
+                let message = match result {
+                    Ok(Ok(())) => None,
+                    Ok(Err(e)) => Some(format!("{:?}", e)),
+                    Err(payload) => Some(panic_report(&*payload)),
+                };
+                if let Some(message) = message {
+                    error!("Render thread failed: {}", message);
+                    shared.lock().unwrap().error = Some(message);
+                }
+            }
+        })?;
+
+        let result = init_rx.recv();
+        let mut render_thread = Self {
+            commands,
+            shared,
+            thread: Some(thread),
+        };
+        match result {
+            Ok(Ok(())) => Ok(render_thread),
+            Ok(Err(e)) => Err(e),
+            // panicked before reporting
+            Err(_) => {
+                render_thread.join();
+                Err(render_thread.error())
+            }
+        }
+    }
+
+    /// Queues `command`. Fails if the render thread has exited.
+    pub fn send(&self, command: Command) -> anyhow::Result<()> {
+        if self.shared.lock().unwrap().error.is_some() {
+            return Err(self.error());
+        }
+        self.commands.send(command).map_err(|_| self.error())
+    }
+
+    /// Releases the current window. Returns once the render thread doesn't use it anymore.
+    pub fn detach_window(&self) -> anyhow::Result<()> {
+        let (tx, rx) = oneshot::channel();
+        self.send(Command::DetachWindow(tx))?;
+        rx.recv().map_err(|_| self.error())
+    }
+
+    /// Continues rendering to `window`.
+    pub fn attach_window(&self, window: AndroidWindow) -> anyhow::Result<()> {
+        let (tx, rx) = oneshot::channel();
+        self.send(Command::AttachWindow(window, tx))?;
+        rx.recv().map_err(|_| self.error())?
+    }
+
+    pub fn frame_stats(&self) -> String {
+        self.shared.lock().unwrap().frame_stats.clone()
+    }
+
+    pub fn gpu_profile(&self) -> String {
+        self.shared.lock().unwrap().gpu_profile.clone()
+    }
+
+    fn error(&self) -> anyhow::Error {
+        match &self.shared.lock().unwrap().error {
+            Some(message) => anyhow!("{}", message),
+            None => anyhow!("Render thread has exited"),
+        }
+    }
+
+    fn join(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            // the thread catches its own panics
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for RenderThread {
+    fn drop(&mut self) {
+        let _ = self.commands.send(Command::Shutdown);
+        self.join();
+        debug!("Render thread joined");
+    }
+}
+
+fn render_loop(
+    mut animator: Box<dyn Animate>,
+    window: Arc<AndroidWindow>,
+    receiver: Receiver<Command>,
+    shared: &Mutex<Shared>,
+) -> anyhow::Result<()> {
+    let mut frame_stats = FrameStats::new();
+    let mut paused = false;
+    let mut last_vsync = i64::MIN;
+    let mut window = Some(window);
+
+    // the sender is dropped only together with the `RenderThread`, after a shutdown
+    while let Ok(command) = receiver.recv() {
+        // handle everything that queued up while the last frame was rendering, and draw only
+        // the latest vsync of it
+        let mut vsync = None;
+        for command in iter::once(command).chain(receiver.try_iter()) {
+            match command {
+                Command::Frame { vsync_nanos } => vsync = Some(vsync_nanos),
+                Command::Pause => paused = true,
+                Command::Resume => {
+                    paused = false;
+                    animator.reset_clock();
+                }
+                Command::Resize(size) => animator.resize(size)?,
+                Command::ChangeScene(entry) => {
+                    animator.change_scene(entry);
+                    frame_stats = FrameStats::new();
+                }
+                Command::Input(event) => animator.input(&event),
+                Command::DetachWindow(reply) => {
+                    animator.detach_window();
+                    // the surface has let go of its reference; this releases the window
+                    window = None;
+                    let _ = reply.send(());
+                }
+                Command::AttachWindow(new_window, reply) => {
+                    let new_window = Arc::new(new_window);
+                    let result = animator.attach_window(Arc::clone(&new_window));
+                    if result.is_ok() {
+                        window = Some(new_window);
+                    }
+                    let _ = reply.send(result);
+                }
+                Command::Shutdown => {
+                    info!("Render thread shutting down");
+                    return Ok(());
+                }
+            }
+        }
+
+        let Some(vsync) = vsync else {
+            continue;
+        };
+        let Some(window) = &window else {
+            continue;
+        };
+        if paused || vsync <= last_vsync {
+            continue;
+        }
+        last_vsync = vsync;
+        trace!("Frame for vsync {}", vsync);
+
+        // `surfaceChanged` can come a few frames after the buffers changed size (e.g. on
+        // rotation); rendering at the stale size would leave the surface outdated until then
+        animator.resize(window.size())?;
+        if !animator.frame()? {
+            continue;
+        }
+        frame_stats.tick();
+
+        if let Some(summary) = frame_stats.report() {
+            let mut shared = shared.lock().unwrap();
+            shared.frame_stats = summary.to_string();
+            shared.gpu_profile = animator.gpu_profile();
+        }
+    }
+    Ok(())
+}
+
+fn create_animator(
+    window: &Arc<AndroidWindow>,
+    entry: &'static SceneEntry,
+) -> anyhow::Result<Box<dyn Animate>> {
+    let instance = Instance::new(&InstanceDescriptor {
+        backends: Backends::VULKAN,
+        ..default!()
+    });
+    let init_info = WgpuStateInitInfo {
+        size: window.size(),
+        surface: instance.create_surface(Arc::clone(window))?,
+        instance,
+    };
+    Ok(Box::new(SceneAnimator::new(init_info, entry)?))
+}
@@ -3,7 +3,6 @@ use bytemuck::{cast_slice, cast_slice_mut};
 use jni::objects::{JClass, JObject, JValueGen};
 use jni::sys::jint;
 use jni::JNIEnv;
-use log::error;
 use std::process::exit;
 use std::thread::spawn;
 use std::time::Instant;
@@ -23,6 +22,7 @@ const INPUT_SIZE: usize = 32;
 const BLOCK_BUFFER_IN_SHADER: u64 = size_of::<FatSha256Buf>() as _;
 
 use crate::default;
+use crate::jni_guard::guard;
 use num_format::{Locale, ToFormattedString};
 use sha2::Digest;
 
@@ -239,7 +239,10 @@ fn wgsl_source(difficulty_bits: u32) -> String {
     source.join("\n")
 }
 
-async fn async_main(args: Args, mut log_callback: impl FnMut(String)) -> anyhow::Result<()> {
+async fn async_main(
+    args: Args,
+    mut log_callback: impl FnMut(String) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
     let runs_per_dispatch = args.dispatch_x * args.workgroup_size;
 
     // let arg_start = hex::decode(args.start.as_ref().map(|x| x.as_str()).unwrap_or_default())?;
@@ -263,7 +266,7 @@ async fn async_main(args: Args, mut log_callback: impl FnMut(String)) -> anyhow:
             hashes.to_formatted_string(&Locale::en),
             ((hashes as f64 / start.elapsed().as_secs_f64()).round() as u64)
                 .to_formatted_string(&Locale::en)
-        ));
+        ))?;
         state.write_input_data(&input_data);
         state.compute_dispatch(args.dispatch_x);
         let hashes_computed = runs_per_dispatch * args.iterations;
@@ -278,10 +281,10 @@ async fn async_main(args: Args, mut log_callback: impl FnMut(String)) -> anyhow:
             hasher.update(convert_fat_buf(&buf));
             let hash = hex::encode(hasher.finalize());
 
-            log_callback("Result:".into());
-            log_callback(format!("  input: {}", hex::encode(convert_fat_buf(&buf))));
-            log_callback(format!("  sha256: {}", hash));
-            log_callback(format!("  elapsed: {:?}", start.elapsed()));
+            log_callback("Result:".into())?;
+            log_callback(format!("  input: {}", hex::encode(convert_fat_buf(&buf))))?;
+            log_callback(format!("  sha256: {}", hash))?;
+            log_callback(format!("  elapsed: {:?}", start.elapsed()))?;
 
             break Ok(());
         }
@@ -300,12 +303,11 @@ pub extern "system" fn Java_pers_zhc_android_myapplication_JNI_sha256Demo(
     difficulty: jint,
     log_callback: JObject,
 ) {
-    let result: anyhow::Result<()> = try {
-        let env_ref = &mut env;
+    guard(&mut env, (), |env| {
         let log_callback_ref = &log_callback;
-        let mut print_log = move |s: &str| -> anyhow::Result<()> {
-            let content = env_ref.new_string(s)?;
-            env_ref.call_method(
+        let print_log = move |s: String| -> anyhow::Result<()> {
+            let content = env.new_string(s)?;
+            env.call_method(
                 log_callback_ref,
                 "print",
                 "(Ljava/lang/String;)V",
@@ -314,23 +316,14 @@ pub extern "system" fn Java_pers_zhc_android_myapplication_JNI_sha256Demo(
             Ok(())
         };
 
-        pollster::block_on(async {
-            async_main(
-                Args {
-                    workgroup_size: workgroup_size as _,
-                    dispatch_x: dispatch_x as _,
-                    iterations: iterations as _,
-                    difficulty: difficulty as _,
-                },
-                |s| {
-                    print_log(&s).unwrap();
-                },
-            )
-            .await
-        })?;
-    };
-
-    if let Err(e) = result {
-        error!("JNI error: {:?}", e);
-    }
+        pollster::block_on(async_main(
+            Args {
+                workgroup_size: workgroup_size as _,
+                dispatch_x: dispatch_x as _,
+                iterations: iterations as _,
+                difficulty: difficulty as _,
+            },
+            print_log,
+        ))
+    })
 }
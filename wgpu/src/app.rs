@@ -0,0 +1,338 @@
+//! Windowed runner for [`Scene`]s.
+//!
+//! [`run`] opens a window, hosts the scene on it and handles resizing, surface errors,
+//! pausing (Space), frame statistics and benchmarking the same way for every demo.
+
+use crate::frame_stats::{FrameStats, FrameSummary};
+use crate::profiler::PassTiming;
+use crate::scene::{GpuContext, InputEvent, Key, Scene, SceneHost};
+use crate::time_source::TimeSource;
+use crate::{wgpu_instance_with_env_backend, WgpuStateInitInfo};
+use log::error;
+use serde::Serialize;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+use std::sync::Arc;
+use wgpu::{PresentMode, SurfaceError, TextureFormat};
+use winit::application::ApplicationHandler;
+use winit::dpi::PhysicalSize;
+use winit::event::{ElementState, MouseButton, MouseScrollDelta, WindowEvent};
+use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop};
+use winit::keyboard::{self, NamedKey};
+use winit::window::{Window, WindowId};
+
+/// Frames rendered before a benchmark starts measuring, to let pipelines and clocks settle.
+const BENCH_WARMUP_FRAMES: u32 = 30;
+/// Animation time step of benchmark frames, in seconds.
+const BENCH_TIME_STEP: f32 = 1.0 / 60.0;
+/// Pinch scale of one mouse wheel notch.
+const WHEEL_ZOOM_PER_LINE: f32 = 1.1;
+/// Touchpad scroll distance counted as one wheel notch.
+const WHEEL_PIXELS_PER_LINE: f32 = 40.0;
+
+/// Animation time of benchmark frame `frame`. The warm-up holds the first frame, so the
+/// measured frames always follow the same stretch of the scene's path.
+fn bench_time(frame: u64) -> f32 {
+    frame.saturating_sub(BENCH_WARMUP_FRAMES as u64) as f32 * BENCH_TIME_STEP
+}
+
+pub struct AppConfig {
+    pub title: String,
+    /// Initial inner size of the window; the platform default if `None`
+    pub size: Option<(u32, u32)>,
+    pub present_mode: PresentMode,
+    /// Render this many frames with a fixed time step, print a JSON [`BenchReport`] and exit
+    pub bench: Option<u32>,
+    /// Record every frame time and write them to this CSV file on exit
+    pub frame_csv: Option<PathBuf>,
+}
+
+impl AppConfig {
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            size: None,
+            present_mode: PresentMode::AutoVsync,
+            bench: None,
+            frame_csv: None,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct AdapterReport {
+    pub name: String,
+    pub vendor: u32,
+    pub device: u32,
+    pub device_type: String,
+    pub driver: String,
+    pub driver_info: String,
+}
+
+#[derive(Serialize)]
+pub struct BenchReport {
+    pub adapter: AdapterReport,
+    pub backend: String,
+    pub resolution: (u32, u32),
+    pub warmup_frames: u32,
+    pub time_step: f32,
+    pub frame_time: FrameSummary,
+    pub gpu_passes: Vec<PassTiming>,
+}
+
+/// Runs `init`'s scene in a new window until it's closed.
+pub fn run<S, F>(config: AppConfig, init: F)
+where
+    S: Scene + 'static,
+    F: FnOnce(&GpuContext, TextureFormat, (u32, u32)) -> S + 'static,
+{
+    let event_loop = EventLoop::new().unwrap();
+    // When the current loop iteration finishes, immediately begin a new
+    // iteration regardless of whether or not new events are available to
+    // process. Preferred for applications that want to render as fast as
+    // possible, like games.
+    event_loop.set_control_flow(ControlFlow::Poll);
+
+    let mut frame_stats = FrameStats::new();
+    // benchmarks start recording after the warm-up
+    if config.frame_csv.is_some() && config.bench.is_none() {
+        frame_stats.start_recording();
+    }
+    let mut runner = Runner {
+        config,
+        init: Some(init),
+        window: None,
+        host: None,
+        frame_stats,
+        frames: 0,
+        cursor: (0.0, 0.0),
+        occluded: false,
+    };
+    event_loop.run_app(&mut runner).unwrap();
+}
+
+struct Runner<S, F> {
+    config: AppConfig,
+    init: Option<F>,
+    window: Option<Arc<Window>>,
+    host: Option<SceneHost<S>>,
+    frame_stats: FrameStats,
+    /// Frames rendered so far
+    frames: u32,
+    cursor: (f32, f32),
+    occluded: bool,
+}
+
+impl<S, F> Runner<S, F>
+where
+    S: Scene,
+{
+    fn bench_report(&mut self) -> BenchReport {
+        let host = self.host.as_mut().unwrap();
+        let info = host.gpu().adapter.get_info();
+        BenchReport {
+            adapter: AdapterReport {
+                name: info.name,
+                vendor: info.vendor,
+                device: info.device,
+                device_type: format!("{:?}", info.device_type),
+                driver: info.driver,
+                driver_info: info.driver_info,
+            },
+            backend: info.backend.to_string(),
+            resolution: host.size(),
+            warmup_frames: BENCH_WARMUP_FRAMES,
+            time_step: BENCH_TIME_STEP,
+            frame_time: self.frame_stats.recorded_summary(),
+            gpu_passes: host.profiler().timings(),
+        }
+    }
+
+    fn write_frame_csv(&self) {
+        if let Some(path) = &self.config.frame_csv {
+            let file = BufWriter::new(File::create(path).unwrap());
+            self.frame_stats.write_csv(file).unwrap();
+        }
+    }
+
+    fn exit(&mut self, event_loop: &ActiveEventLoop) {
+        if self.config.bench.is_none() && self.config.frame_csv.is_some() {
+            println!("{}", self.frame_stats.recorded_summary());
+            println!("{}", self.frame_stats.histogram_text());
+        }
+        self.write_frame_csv();
+        event_loop.exit();
+    }
+
+    /// Statistics bookkeeping after a presented frame.
+    fn after_frame(&mut self, event_loop: &ActiveEventLoop) {
+        self.frame_stats.tick();
+        self.frames += 1;
+
+        if let Some(bench_frames) = self.config.bench {
+            if self.frames == BENCH_WARMUP_FRAMES {
+                self.frame_stats.start_recording();
+            }
+            if self.frames == BENCH_WARMUP_FRAMES + bench_frames {
+                let report = self.bench_report();
+                println!("{}", serde_json::to_string_pretty(&report).unwrap());
+                self.exit(event_loop);
+            }
+            return;
+        }
+
+        let host = self.host.as_mut().unwrap();
+        if let Some(summary) = self.frame_stats.report() {
+            let status = host.scene().status();
+            match &status {
+                Some(status) => println!("{}, {}", summary, status),
+                None => println!("{}", summary),
+            }
+            if let Some(w) = &self.window {
+                let mut title = self.config.title.clone();
+                if let Some(status) = &status {
+                    title += &format!(" - {}", status);
+                }
+                title += &format!(" - {:.1} FPS", summary.fps);
+                if host.is_paused() {
+                    title += " (paused)";
+                }
+                w.set_title(&title);
+            }
+        }
+        if let Some(report) = host.profiler().report() {
+            println!("{}", report);
+        }
+    }
+}
+
+impl<S, F> ApplicationHandler for Runner<S, F>
+where
+    S: Scene,
+    F: FnOnce(&GpuContext, TextureFormat, (u32, u32)) -> S,
+{
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.window.is_some() {
+            return;
+        }
+
+        let mut attributes = Window::default_attributes().with_title(&self.config.title);
+        if let Some((width, height)) = self.config.size {
+            attributes = attributes.with_inner_size(PhysicalSize::new(width, height));
+        }
+        if self.config.bench.is_some() {
+            // keep the benchmarked resolution fixed
+            attributes = attributes.with_resizable(false);
+        }
+        let window = Arc::new(event_loop.create_window(attributes).unwrap());
+
+        let instance = wgpu_instance_with_env_backend();
+        let surface = instance.create_surface(Arc::clone(&window)).unwrap();
+        let info = WgpuStateInitInfo {
+            instance,
+            surface,
+            size: window.inner_size().into(),
+        };
+        let init = self.init.take().unwrap();
+        let mut host = pollster::block_on(SceneHost::new(info, init)).unwrap();
+        host.set_present_mode(self.config.present_mode);
+        if self.config.bench.is_some() {
+            host.set_time_source(TimeSource::scripted(bench_time));
+        }
+        self.host = Some(host);
+
+        window.request_redraw();
+        self.window = Some(window);
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _id: WindowId, event: WindowEvent) {
+        let Some(host) = self.host.as_mut() else {
+            return;
+        };
+        match event {
+            WindowEvent::CloseRequested => self.exit(event_loop),
+            WindowEvent::Resized(size) => {
+                // Reconfigures the size of the surface. We do not re-render
+                // here as this event is always followed up by redraw request.
+                host.resize(size.into());
+            }
+            WindowEvent::Occluded(occluded) => {
+                self.occluded = occluded;
+                if !occluded && let Some(w) = &self.window {
+                    w.request_redraw();
+                }
+            }
+            WindowEvent::RedrawRequested => {
+                let Some(w) = self.window.clone() else {
+                    return;
+                };
+                // nothing to draw into while minimized or hidden; wait for the next
+                // resize or occlusion change to resume
+                let size = w.inner_size();
+                if self.occluded || size.width == 0 || size.height == 0 {
+                    return;
+                }
+
+                match host.frame(|| w.pre_present_notify()) {
+                    Ok(true) => self.after_frame(event_loop),
+                    Ok(false) => {}
+                    Err(SurfaceError::OutOfMemory) => {
+                        error!("Out of memory");
+                        self.exit(event_loop);
+                    }
+                    Err(e) => error!("{:?}", e),
+                }
+                w.request_redraw();
+            }
+            WindowEvent::KeyboardInput { event, .. } => {
+                if event.state != ElementState::Pressed {
+                    return;
+                }
+                let key = match event.logical_key.as_ref() {
+                    keyboard::Key::Named(NamedKey::Space) => Key::Space,
+                    keyboard::Key::Named(NamedKey::Enter) => Key::Enter,
+                    keyboard::Key::Named(NamedKey::Escape) => Key::Escape,
+                    keyboard::Key::Named(NamedKey::Tab) => Key::Tab,
+                    keyboard::Key::Named(NamedKey::ArrowLeft) => Key::ArrowLeft,
+                    keyboard::Key::Named(NamedKey::ArrowRight) => Key::ArrowRight,
+                    keyboard::Key::Named(NamedKey::ArrowUp) => Key::ArrowUp,
+                    keyboard::Key::Named(NamedKey::ArrowDown) => Key::ArrowDown,
+                    keyboard::Key::Character(s) => match s.chars().next() {
+                        Some(c) => Key::Character(c),
+                        None => return,
+                    },
+                    _ => return,
+                };
+                host.input(&InputEvent::KeyPressed(key));
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor = (position.x as f32, position.y as f32);
+                let (x, y) = self.cursor;
+                host.input(&InputEvent::PointerMoved { id: 0, x, y });
+            }
+            WindowEvent::MouseInput {
+                state,
+                button: MouseButton::Left,
+                ..
+            } => {
+                let (x, y) = self.cursor;
+                let event = match state {
+                    ElementState::Pressed => InputEvent::PointerPressed { id: 0, x, y },
+                    ElementState::Released => InputEvent::PointerReleased { id: 0, x, y },
+                };
+                host.input(&event);
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let lines = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => y,
+                    MouseScrollDelta::PixelDelta(p) => p.y as f32 / WHEEL_PIXELS_PER_LINE,
+                };
+                host.input(&InputEvent::Pinch {
+                    scale: WHEEL_ZOOM_PER_LINE.powf(lines),
+                });
+            }
+            _ => {}
+        }
+    }
+}
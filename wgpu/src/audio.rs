@@ -0,0 +1,78 @@
+//! Stereo audio as the beam of an XY oscilloscope: the left channel deflects it horizontally,
+//! the right one vertically.
+
+use anyhow::bail;
+use hound::{SampleFormat, WavReader};
+use std::borrow::Cow;
+use std::path::Path;
+
+pub struct StereoTrack {
+    sample_rate: u32,
+    /// A point per sample frame, in -1..=1
+    points: Vec<[f32; 2]>,
+}
+
+impl StereoTrack {
+    /// Decodes a stereo WAV file of integer or float samples.
+    pub fn from_wav(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let mut reader = WavReader::open(path)?;
+        let spec = reader.spec();
+        if spec.channels != 2 {
+            bail!(
+                "Expected a stereo WAV file, got {} channel(s)",
+                spec.channels
+            );
+        }
+        let samples = match spec.sample_format {
+            SampleFormat::Float => reader.samples::<f32>().collect::<Result<Vec<_>, _>>()?,
+            SampleFormat::Int => {
+                let scale = 1.0 / (1_i64 << (spec.bits_per_sample - 1)) as f32;
+                reader
+                    .samples::<i32>()
+                    .map(|x| x.map(|x| x as f32 * scale))
+                    .collect::<Result<Vec<_>, _>>()?
+            }
+        };
+        let points = samples.chunks_exact(2).map(|x| [x[0], x[1]]).collect();
+        Ok(Self {
+            sample_rate: spec.sample_rate,
+            points,
+        })
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Length in seconds.
+    pub fn duration(&self) -> f32 {
+        self.points.len() as f32 / self.sample_rate as f32
+    }
+
+    /// Position in the track after playing it on a loop for `time` seconds.
+    pub fn looped(&self, time: f32) -> f32 {
+        time % self.duration().max(f32::MIN_POSITIVE)
+    }
+
+    /// The points the beam passes through from `start` to `end` seconds into the track. Times
+    /// outside of it are clamped.
+    pub fn points(&self, start: f32, end: f32) -> &[[f32; 2]] {
+        let index =
+            |t: f32| ((t * self.sample_rate as f32).max(0.0) as usize).min(self.points.len());
+        let start = index(start);
+        &self.points[start..index(end).max(start)]
+    }
+
+    /// The points of the `span` seconds before `end`, like [`Self::points`] but continuing
+    /// from the end of the track when reaching back past its start, as when playing on a loop.
+    pub fn looped_points(&self, end: f32, span: f32) -> Cow<'_, [[f32; 2]]> {
+        let start = end - span;
+        if start >= 0.0 {
+            return Cow::Borrowed(self.points(start, end));
+        }
+        let duration = self.duration();
+        let mut points = self.points(duration + start, duration).to_vec();
+        points.extend_from_slice(self.points(0.0, end));
+        Cow::Owned(points)
+    }
+}
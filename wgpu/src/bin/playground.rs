@@ -0,0 +1,64 @@
+//! All scenes in one window.
+//!
+//! Keys 1-9 select a scene, Tab cycles through them and Space pauses the animation.
+
+use clap::Parser;
+use std::env;
+use wgpu::PresentMode;
+use wgpu_playground::app::{self, AppConfig};
+use wgpu_playground::registry::{find_scene, SceneOptions, SceneSwitcher, SCENES};
+use wgpu_playground::parse_fps;
+
+#[derive(Parser, Debug)]
+#[command(about = "Launcher for all wgpu playground scenes")]
+struct Args {
+    /// Scene to start with [default: the first one of `--list`]
+    scene: Option<String>,
+
+    /// Print the available scenes and exit
+    #[arg(long)]
+    list: bool,
+
+    /// Adapt the internal resolution of heavy scenes to hold this frame rate
+    /// [default: per scene]
+    #[arg(long, value_parser = parse_fps)]
+    target_fps: Option<f32>,
+}
+
+fn main() {
+    unsafe {
+        env::set_var("RUST_LOG", "info");
+    }
+    env_logger::init();
+
+    let args = Args::parse();
+    if args.list {
+        for (i, scene) in SCENES.iter().enumerate() {
+            println!("{}: {:<24} {}", i + 1, scene.name, scene.description);
+        }
+        return;
+    }
+
+    let index = match &args.scene {
+        Some(name) => match find_scene(name) {
+            Some(x) => x,
+            None => {
+                let names = SCENES.iter().map(|x| x.name).collect::<Vec<_>>();
+                eprintln!("Unknown scene `{}`; available: {}", name, names.join(", "));
+                std::process::exit(1);
+            }
+        },
+        None => 0,
+    };
+    let options = SceneOptions {
+        target_fps: args.target_fps,
+    };
+
+    let config = AppConfig {
+        present_mode: PresentMode::AutoNoVsync,
+        ..AppConfig::new("wgpu playground")
+    };
+    app::run(config, move |gpu, format, size| {
+        SceneSwitcher::new(gpu, format, size, index, options)
+    });
+}
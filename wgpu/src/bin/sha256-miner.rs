@@ -8,9 +8,11 @@ use tokio::sync::oneshot;
 use wgpu::wgt::PollType;
 use wgpu::{
     BindGroup, BindGroupDescriptor, BindGroupEntry, BindingResource, Buffer, BufferBinding,
-    BufferDescriptor, BufferUsages, ComputePipeline, ComputePipelineDescriptor, Device, MapMode,
-    PipelineCompilationOptions, Queue, ShaderModuleDescriptor, ShaderSource,
+    BufferDescriptor, BufferUsages, ComputePassDescriptor, ComputePipeline,
+    ComputePipelineDescriptor, Device, DeviceDescriptor, MapMode, PipelineCompilationOptions,
+    Queue, ShaderModuleDescriptor, ShaderSource,
 };
+use wgpu_playground::profiler::GpuProfiler;
 use wgpu_playground::{default, set_up_logger, wgpu_instance_with_env_backend};
 
 /// Sha256 buffer type the shader uses.
@@ -57,13 +59,19 @@ struct State {
     result_buffer: Buffer,
     map_read_buffer: Buffer,
     bind_group: BindGroup,
+    profiler: GpuProfiler,
 }
 
 impl State {
     async fn new(args: &Args) -> anyhow::Result<Self> {
         let instance = wgpu_instance_with_env_backend();
         let adapter = instance.request_adapter(&default!()).await?;
-        let (device, queue) = adapter.request_device(&default!()).await?;
+        let (device, queue) = adapter
+            .request_device(&DeviceDescriptor {
+                required_features: GpuProfiler::supported_features(&adapter),
+                ..default!()
+            })
+            .await?;
 
         let shader_module = device.create_shader_module(ShaderModuleDescriptor {
             label: None,
@@ -131,6 +139,7 @@ impl State {
             ],
         });
 
+        let profiler = GpuProfiler::new(&device, &queue);
         Ok(Self {
             queue,
             device,
@@ -139,6 +148,7 @@ impl State {
             bind_group,
             result_buffer,
             map_read_buffer,
+            profiler,
         })
     }
 
@@ -151,19 +161,24 @@ impl State {
             .write_buffer(&self.input_buffer, 0, cast_slice(&input_data));
     }
 
-    fn compute_dispatch(&self, workgroups_x: u32) {
+    fn compute_dispatch(&mut self, workgroups_x: u32) {
         let mut encoder = self.device.create_command_encoder(&default!());
 
-        let mut pass = encoder.begin_compute_pass(&default!());
+        let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+            label: None,
+            timestamp_writes: self.profiler.compute_pass("Mining"),
+        });
         pass.set_pipeline(&self.pipeline);
         pass.set_bind_group(0, &self.bind_group, default!());
         pass.dispatch_workgroups(workgroups_x, 1, 1);
         drop(pass);
 
         encoder.copy_buffer_to_buffer(&self.result_buffer, 0, &self.map_read_buffer, 0, None);
+        self.profiler.resolve(&mut encoder);
 
         let command_buffer = encoder.finish();
         self.queue.submit([command_buffer]);
+        self.profiler.end_frame();
     }
 
     async fn read_result(&self, to: &mut [u8]) -> anyhow::Result<()> {
@@ -282,7 +297,7 @@ async fn main() -> anyhow::Result<()> {
         return Err(anyhow!("Length of `start` must be <= 32"));
     }
 
-    let state = State::new(&args).await?;
+    let mut state = State::new(&args).await?;
     let mut input_data = [0_u8; INPUT_SIZE];
     input_data[..arg_start.len()].copy_from_slice(&arg_start);
     let mut result = [0_u32; SHA256_BYTES];
@@ -308,6 +323,9 @@ async fn main() -> anyhow::Result<()> {
         if result.iter().any(|x| *x != 0) {
             print_result_and_exit(result, start);
         }
+        if let Some(report) = state.profiler.report() {
+            println!("{}", report);
+        }
         counter += 1;
     }
 }
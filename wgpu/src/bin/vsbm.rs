@@ -1,94 +1,63 @@
-#![feature(try_blocks)]
-
 //! https://cznull.github.io/vsbm wgpu port
 //!
 //! At 1024x1024 surface dimension, DX12 on Windows 10 has ~5 fps higher than
 //! Vulkan on Windows 10 & Vulkan on Linux. Test hardware: NVIDIA GeForce RTX 3060 Mobile / Max-Q.
+//!
+//! To reproduce such a comparison, run e.g.
+//! `WGPU_BACKEND=dx12 vsbm --bench 1000 --size 1024x1024` once per backend and compare the
+//! JSON reports.
 
-use chrono::Local;
+use clap::Parser;
 use std::env;
-use std::sync::Arc;
-use wgpu_playground::vsbm::State;
-use wgpu_playground::{wgpu_instance_with_env_backend, WgpuStateInitInfo};
-use winit::application::ApplicationHandler;
-use winit::event_loop::ActiveEventLoop;
-use winit::window::{Window, WindowId};
-use winit::{
-    event::*,
-    event_loop::{ControlFlow, EventLoop},
-};
-#[derive(Default)]
-struct App {
-    state: Option<State>,
-    window: Option<Arc<Window>>,
-    last_frame_time: u64,
-}
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+use wgpu::PresentMode;
+use wgpu_playground::app::{self, AppConfig};
+use wgpu_playground::{parse_fps, parse_size};
+use wgpu_playground::vsbm::export::{export_png, ExportOptions};
+use wgpu_playground::vsbm::{Camera, VsbmScene};
 
-impl ApplicationHandler for App {
-    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
-        // Create window object
-        let window = Arc::new(
-            event_loop
-                .create_window(Window::default_attributes())
-                .unwrap(),
-        );
+#[derive(Parser, Debug)]
+#[command(about = "vsbm wgpu port")]
+struct Args {
+    /// Render a still image to this PNG file instead of opening a window
+    #[arg(long)]
+    export: Option<PathBuf>,
 
-        pollster::block_on(async {
-            let result: anyhow::Result<()> = try {
-                // let size = window.inner_size();
-                let size = (1024, 1024);
-                let instance = wgpu_instance_with_env_backend();
-                let surface = instance.create_surface(Arc::clone(&window))?;
-                let state = State::new(WgpuStateInitInfo {
-                    instance,
-                    size,
-                    surface,
-                })
-                .await;
-                self.state = Some(state);
-            };
-            result
-        })
-        .unwrap();
+    /// Size of the exported image or the benchmark window, as WIDTHxHEIGHT
+    /// [default: 4096x4096 for `--export`]
+    #[arg(long, value_parser = parse_size)]
+    size: Option<(u32, u32)>,
 
-        window.request_redraw();
-        self.window = Some(window);
-    }
+    /// Animation time of the exported frame
+    #[arg(long, default_value_t = 0.0)]
+    time: f32,
 
-    fn window_event(
-        &mut self,
-        event_loop: &ActiveEventLoop,
-        window_id: WindowId,
-        event: WindowEvent,
-    ) {
-        let state = self.state.as_mut().unwrap();
-        match event {
-            WindowEvent::CloseRequested => event_loop.exit(),
-            WindowEvent::Resized(physical_size) => state.resize((1024, 1024)),
-            WindowEvent::RedrawRequested => {
-                let Some(w) = &self.window else {
-                    return;
-                };
+    /// Camera of the exported frame as `yaw,pitch,distance`
+    /// [default: the auto-rotating camera at `--time`]
+    #[arg(long, allow_hyphen_values = true)]
+    camera: Option<Camera>,
 
-                state.update();
-                match state.render(|| w.pre_present_notify()) {
-                    Ok(_) => {}
-                    Err(wgpu::SurfaceError::Lost) => state.resize(state.size),
-                    Err(wgpu::SurfaceError::OutOfMemory) => event_loop.exit(),
-                    Err(e) => eprintln!("{:?}", e),
-                }
+    /// Edge length of the offscreen tiles in pixels
+    #[arg(long, default_value_t = 1024)]
+    tile_size: u32,
 
-                // print the FPS
-                let ts = Local::now().timestamp_nanos_opt().unwrap() as u64;
-                let fps = 1_000_000_000.0 / (ts - self.last_frame_time) as f64;
-                println!("FPS: {:.2}", fps.floor());
-                self.last_frame_time = ts;
+    /// Number of jittered samples accumulated per pixel
+    #[arg(long, default_value_t = 1)]
+    samples: u32,
 
-                w.request_redraw();
-            }
-            _ => {}
-        }
-    }
+    /// Adapt the internal resolution to hold this frame rate
+    #[arg(long, conflicts_with = "bench", value_parser = parse_fps)]
+    target_fps: Option<f32>,
+
+    /// Render this many frames along a fixed camera path, print a JSON report and exit
+    #[arg(long)]
+    bench: Option<u32>,
+
+    /// Write every frame time of the run to this CSV file on exit
+    #[arg(long)]
+    frame_csv: Option<PathBuf>,
 }
 
 pub fn main() {
@@ -97,10 +66,31 @@ pub fn main() {
     }
     env_logger::init();
 
-    let event_loop = EventLoop::new().unwrap();
-
-    event_loop.set_control_flow(ControlFlow::Poll);
+    let args = Args::parse();
+    if let Some(path) = args.export {
+        let options = ExportOptions {
+            size: args.size.unwrap_or((4096, 4096)),
+            camera: args.camera.unwrap_or(Camera::auto_rotate(args.time)),
+            tile_size: args.tile_size,
+            samples: args.samples,
+        };
+        let output = BufWriter::new(File::create(&path).unwrap());
+        pollster::block_on(export_png(&options, output)).unwrap();
+        println!("Exported to {}", path.display());
+        return;
+    }
 
-    let mut app = App::default();
-    event_loop.run_app(&mut app).unwrap();
+    let config = AppConfig {
+        size: args.size,
+        present_mode: PresentMode::AutoNoVsync,
+        bench: args.bench,
+        frame_csv: args.frame_csv,
+        ..AppConfig::new("vsbm")
+    };
+    let target_fps = args.target_fps;
+    app::run(config, move |gpu, format, size| {
+        let mut scene = VsbmScene::new(gpu, format, size);
+        scene.set_target_fps(target_fps);
+        scene
+    });
 }
@@ -0,0 +1,265 @@
+//! Rolling frame-time statistics.
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+/// Frames the rolling statistics are computed over.
+pub const DEFAULT_WINDOW: usize = 300;
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+/// Upper bounds (exclusive, in ms) of the histogram buckets; the last one is open.
+const HISTOGRAM_BOUNDS_MS: [f32; 8] = [4.0, 8.0, 12.0, 17.0, 25.0, 34.0, 50.0, 100.0];
+
+pub struct FrameStats {
+    /// Most recent frame times in milliseconds
+    window: VecDeque<f32>,
+    capacity: usize,
+    last_frame: Option<Instant>,
+    last_report: Instant,
+    /// Every frame time since recording started, for [`Self::write_csv`]
+    recorded: Option<Vec<f32>>,
+}
+
+#[derive(Copy, Clone, Debug, Default, Serialize)]
+pub struct FrameSummary {
+    pub frames: usize,
+    pub mean_ms: f32,
+    /// Frames per second from the mean frame time
+    pub fps: f32,
+    /// Average FPS over the slowest 1% of frames
+    pub low_1pct_fps: f32,
+    pub p99_ms: f32,
+    pub min_ms: f32,
+    pub max_ms: f32,
+}
+
+impl FrameStats {
+    pub fn new() -> Self {
+        Self::with_window(DEFAULT_WINDOW)
+    }
+
+    pub fn with_window(capacity: usize) -> Self {
+        Self {
+            window: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+            last_frame: None,
+            last_report: Instant::now(),
+            recorded: None,
+        }
+    }
+
+    /// Keeps every frame time from now on, so a whole run can be dumped with [`Self::write_csv`].
+    pub fn start_recording(&mut self) {
+        self.recorded = Some(Vec::new());
+    }
+
+    /// Marks a frame boundary and records the time since the previous one.
+    pub fn tick(&mut self) -> Option<Duration> {
+        let now = Instant::now();
+        let dt = self.last_frame.replace(now).map(|last| now - last)?;
+        self.push(dt);
+        Some(dt)
+    }
+
+    /// Records an externally measured frame time, e.g. from vsync timestamps.
+    pub fn push(&mut self, frame_time: Duration) {
+        let ms = frame_time.as_secs_f32() * 1000.0;
+        if self.window.len() == self.capacity {
+            self.window.pop_front();
+        }
+        self.window.push_back(ms);
+        if let Some(recorded) = &mut self.recorded {
+            recorded.push(ms);
+        }
+    }
+
+    /// Statistics over the rolling window.
+    pub fn summary(&self) -> FrameSummary {
+        summarize(self.window.iter().copied())
+    }
+
+    /// Statistics over all recorded frames, or over the window if not recording.
+    pub fn recorded_summary(&self) -> FrameSummary {
+        match &self.recorded {
+            Some(x) => summarize(x.iter().copied()),
+            None => self.summary(),
+        }
+    }
+
+    /// Frame counts per bucket of the rolling window, as `(upper bound in ms, count)`.
+    /// The last bucket is unbounded.
+    pub fn histogram(&self) -> Vec<(f32, usize)> {
+        let mut buckets = HISTOGRAM_BOUNDS_MS
+            .iter()
+            .copied()
+            .chain([f32::INFINITY])
+            .map(|x| (x, 0))
+            .collect::<Vec<_>>();
+        for &ms in &self.window {
+            let i = HISTOGRAM_BOUNDS_MS
+                .iter()
+                .position(|&b| ms < b)
+                .unwrap_or(HISTOGRAM_BOUNDS_MS.len());
+            buckets[i].1 += 1;
+        }
+        buckets
+    }
+
+    /// Text rendering of [`Self::histogram`], one bucket per line.
+    pub fn histogram_text(&self) -> String {
+        let histogram = self.histogram();
+        let max = histogram.iter().map(|x| x.1).max().unwrap_or(0).max(1);
+        let mut lower = 0.0;
+        let mut lines = Vec::new();
+        for (upper, count) in histogram {
+            let range = if upper.is_finite() {
+                format!("{:>5.0}-{:<5.0}ms", lower, upper)
+            } else {
+                format!("{:>5.0}+     ms", lower)
+            };
+            let bar = "#".repeat(count * 40 / max);
+            lines.push(format!("{} {:>5} {}", range, count, bar));
+            lower = upper;
+        }
+        lines.join("\n")
+    }
+
+    /// Writes the recorded frame times (or the window if not recording) as CSV.
+    pub fn write_csv(&self, mut writer: impl Write) -> std::io::Result<()> {
+        writeln!(writer, "frame,frame_time_ms")?;
+        let frames: Box<dyn Iterator<Item = &f32>> = match &self.recorded {
+            Some(x) => Box::new(x.iter()),
+            None => Box::new(self.window.iter()),
+        };
+        for (i, ms) in frames.enumerate() {
+            writeln!(writer, "{},{}", i, ms)?;
+        }
+        Ok(())
+    }
+
+    /// [`Self::summary`] at most once per second, for periodic printing.
+    pub fn report(&mut self) -> Option<FrameSummary> {
+        if self.window.is_empty() || self.last_report.elapsed() < REPORT_INTERVAL {
+            return None;
+        }
+        self.last_report = Instant::now();
+        Some(self.summary())
+    }
+}
+
+impl Default for FrameStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn summarize(frame_times: impl Iterator<Item = f32>) -> FrameSummary {
+    let mut sorted = frame_times.collect::<Vec<_>>();
+    if sorted.is_empty() {
+        return FrameSummary::default();
+    }
+    sorted.sort_by(f32::total_cmp);
+
+    let n = sorted.len();
+    let mean_ms = sorted.iter().sum::<f32>() / n as f32;
+    let slowest = &sorted[(n - n.div_ceil(100))..];
+    let slowest_mean = slowest.iter().sum::<f32>() / slowest.len() as f32;
+    let p99_index = ((n as f32 * 0.99).ceil() as usize).clamp(1, n) - 1;
+
+    FrameSummary {
+        frames: n,
+        mean_ms,
+        fps: 1000.0 / mean_ms,
+        low_1pct_fps: 1000.0 / slowest_mean,
+        p99_ms: sorted[p99_index],
+        min_ms: sorted[0],
+        max_ms: sorted[n - 1],
+    }
+}
+
+impl Display for FrameSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "FPS: {:.1} (1% low {:.1}), frame time: {:.2} ms (p99 {:.2}, max {:.2})",
+            self.fps, self.low_1pct_fps, self.mean_ms, self.p99_ms, self.max_ms
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats_of(ms: impl IntoIterator<Item = u64>, window: usize) -> FrameStats {
+        let mut stats = FrameStats::with_window(window);
+        for x in ms {
+            stats.push(Duration::from_millis(x));
+        }
+        stats
+    }
+
+    fn assert_near(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-3, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn summary_of_known_distribution() {
+        let summary = stats_of(1..=200, 200).summary();
+        assert_eq!(summary.frames, 200);
+        assert_near(summary.mean_ms, 100.5);
+        assert_near(summary.fps, 1000.0 / 100.5);
+        // the slowest 1% are the 2 frames of 199 and 200 ms
+        assert_near(summary.low_1pct_fps, 1000.0 / 199.5);
+        // nearest rank: the 198th of 200
+        assert_near(summary.p99_ms, 198.0);
+        assert_near(summary.min_ms, 1.0);
+        assert_near(summary.max_ms, 200.0);
+
+        let single = stats_of([10], 10).summary();
+        assert_near(single.p99_ms, 10.0);
+        assert_near(single.low_1pct_fps, 100.0);
+    }
+
+    #[test]
+    fn empty() {
+        let stats = FrameStats::new();
+        let summary = stats.summary();
+        assert_eq!(summary.frames, 0);
+        assert_eq!(summary.fps, 0.0);
+        assert!(stats.histogram().iter().all(|&(_, count)| count == 0));
+        assert_eq!(stats.histogram().len(), HISTOGRAM_BOUNDS_MS.len() + 1);
+    }
+
+    #[test]
+    fn histogram_buckets() {
+        let histogram = stats_of([3, 4, 16, 17, 150], 10).histogram();
+        let count = |upper: f32| histogram.iter().find(|x| x.0 == upper).unwrap().1;
+        // the bounds are exclusive
+        assert_eq!(count(4.0), 1);
+        assert_eq!(count(8.0), 1);
+        assert_eq!(count(17.0), 1);
+        assert_eq!(count(25.0), 1);
+        assert_eq!(count(f32::INFINITY), 1);
+        assert_eq!(histogram.iter().map(|x| x.1).sum::<usize>(), 5);
+    }
+
+    #[test]
+    fn window_wraps_around() {
+        let mut stats = FrameStats::with_window(3);
+        stats.start_recording();
+        for x in [100, 1, 2, 3] {
+            stats.push(Duration::from_millis(x));
+        }
+        let summary = stats.summary();
+        assert_eq!(summary.frames, 3);
+        assert_near(summary.mean_ms, 2.0);
+        assert_near(summary.max_ms, 3.0);
+
+        let recorded = stats.recorded_summary();
+        assert_eq!(recorded.frames, 4);
+        assert_near(recorded.max_ms, 100.0);
+    }
+}
@@ -1,10 +1,28 @@
 #![feature(decl_macro)]
 
+#[cfg(not(target_os = "android"))]
+pub mod app;
+pub mod audio;
+pub mod blit;
+pub mod frame_stats;
+pub mod lissajous;
+pub mod mesh;
+pub mod offline;
+pub mod overlay;
+pub mod phosphor;
+pub mod polyline;
+pub mod profiler;
+pub mod readback;
+pub mod registry;
+pub mod render_scale;
+pub mod scene;
+pub mod time_source;
 pub mod triangle_rotation;
 pub mod vsbm;
+pub mod webgpu_bg;
 
+use clap::ValueEnum;
 use std::env;
-use std::time::{Duration, Instant};
 use wgpu::{Backends, Color, Instance, InstanceDescriptor, Surface};
 
 pub fn set_up_logger() {
@@ -26,6 +44,23 @@ pub fn wgpu_instance_with_env_backend() -> Instance {
     instance
 }
 
+/// Parses `WIDTHxHEIGHT`, for command line arguments.
+pub fn parse_size(s: &str) -> Result<(u32, u32), String> {
+    let (w, h) = s.split_once(['x', 'X']).ok_or("expected WIDTHxHEIGHT")?;
+    let w = w.parse::<u32>().map_err(|e| e.to_string())?;
+    let h = h.parse::<u32>().map_err(|e| e.to_string())?;
+    Ok((w, h))
+}
+
+/// Parses a positive, finite frame rate, for command line arguments.
+pub fn parse_fps(s: &str) -> Result<f32, String> {
+    let fps = s.parse::<f32>().map_err(|e| e.to_string())?;
+    if !(fps > 0.0 && fps.is_finite()) {
+        return Err("expected a positive frame rate".into());
+    }
+    Ok(fps)
+}
+
 pub trait ColorExt {
     fn from_vec4d(x: [f64; 4]) -> Self;
 }
@@ -41,6 +76,23 @@ impl ColorExt for Color {
     }
 }
 
+/// Names and cycling for the [`ValueEnum`]s the demos take on the command line.
+pub trait ValueEnumExt: ValueEnum + PartialEq {
+    /// The name on the command line.
+    fn name(&self) -> String {
+        self.to_possible_value().unwrap().get_name().into()
+    }
+
+    /// The variant after this one, wrapping around.
+    fn next(&self) -> Self {
+        let all = Self::value_variants();
+        let index = all.iter().position(|x| x == self).unwrap();
+        all[(index + 1) % all.len()].clone()
+    }
+}
+
+impl<T: ValueEnum + PartialEq> ValueEnumExt for T {}
+
 pub fn random_color() -> [f32; 3] {
     [
         rand::random::<f32>(),
@@ -49,35 +101,14 @@ pub fn random_color() -> [f32; 3] {
     ]
 }
 
+/// What a [`scene::SceneHost`] creates its [`scene::GpuContext`] from. The context, and with it
+/// the device, lives as long as the host; scenes can be swapped without recreating it.
 pub struct WgpuStateInitInfo {
     pub instance: Instance,
     pub surface: Surface<'static>,
     pub size: (u32, u32),
 }
 
-pub struct FpsCounter {
-    instant: Instant,
-    counter: usize,
-}
-
-impl FpsCounter {
-    pub fn new() -> Self {
-        Self {
-            instant: Instant::now(),
-            counter: 0,
-        }
-    }
-
-    pub fn hint_and_get(&mut self) -> (Duration, f32) {
-        self.counter += 1;
-        let duration = self.instant.elapsed();
-        (
-            duration,
-            (self.counter as f64 / duration.as_secs_f64()) as f32,
-        )
-    }
-}
-
 #[cfg(not(target_os = "android"))]
 pub mod winit_extensions {
     use winit::dpi::PhysicalSize;
@@ -0,0 +1,908 @@
+//! Animated parametric curve, generated either on the CPU, in the vertex shader or by a
+//! compute shader, to compare the three. The trace can persist like on an oscilloscope, and
+//! the points can come from stereo audio instead, like oscilloscope music.
+//!
+//! The arrow keys tweak the curve while it's running, O shows the settings, and S and L save
+//! them to and load them from a TOML [`Preset`].
+
+use crate::audio::StereoTrack;
+use crate::overlay::Overlay;
+use crate::phosphor::{Phosphor, PhosphorSettings, ADDITIVE_BLENDING};
+use crate::polyline::{Join, LineStyle, PolylineRenderer};
+use crate::scene::{Frame, GpuContext, InputEvent, Key, Scene};
+use crate::{ColorExt, ValueEnumExt};
+use bytemuck::{Pod, Zeroable};
+use clap::ValueEnum;
+use log::{info, warn};
+use palette::{FromColor, Hsv, ShiftHue, Srgb};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::f32::consts::TAU;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use wgpu::{
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BlendState, Buffer, BufferDescriptor,
+    BufferUsages, Color, ComputePassDescriptor, ComputePipeline, ComputePipelineDescriptor, Device,
+    RenderPass, ShaderModuleDescriptor, ShaderSource, TextureFormat,
+};
+
+/// Capacity of the point buffer.
+pub const MAX_SEGMENTS: u32 = 65536;
+/// Must match `cs_generate` in lissajous.wgsl.
+const WORKGROUP_SIZE: u32 = 64;
+/// Stretch of audio drawn while the time stands still, e.g. when paused, in seconds.
+const PAUSED_AUDIO_SPAN: f32 = 0.02;
+/// 赛博朋克青色
+const COLOR: [f32; 4] = [0.0, 1.0, 0.8, 1.0];
+/// Where S saves the preset and L loads it from unless told otherwise.
+pub const DEFAULT_PRESET_PATH: &str = "lissajous.toml";
+const OVERLAY_BACKGROUND: [f32; 4] = [0.0, 0.0, 0.0, 0.6];
+const OVERLAY_SELECTION: [f32; 4] = [0.0, 0.4, 0.35, 0.8];
+const OVERLAY_TEXT: [f32; 4] = [0.9, 0.9, 0.9, 1.0];
+const OVERLAY_HINT: [f32; 4] = [0.6, 0.6, 0.6, 1.0];
+
+const CURVE_WGSL: &str = include_str!("shaders/lissajous-curve.wgsl");
+/// Points of the polyline for [`Generator::VertexShader`].
+const CURVE_POINTS_WGSL: &str = "
+@group(1) @binding(0) var<uniform> curve: Curve;
+
+fn polyline_point(i: u32) -> vec2f {
+    return curve_point(curve, i);
+}
+";
+
+/// `amplitude * exp(-damping * t) * (sin(fx * t + px), sin(fy * t + py))` for t from 0 to
+/// 2π * `turns`, with frequencies and phases drifting over the animation time.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Curve {
+    pub frequency: [f32; 2],
+    /// Change of `frequency` per second
+    pub frequency_speed: [f32; 2],
+    pub phase: [f32; 2],
+    /// Change of `phase` per second
+    pub phase_speed: [f32; 2],
+    pub damping: f32,
+    pub amplitude: f32,
+    pub turns: f32,
+    /// Number of points on the curve
+    pub segments: u32,
+}
+
+impl Default for Curve {
+    /// The ever-changing figure the demo has always shown.
+    fn default() -> Self {
+        Self {
+            frequency: [0.0, 0.0],
+            frequency_speed: [0.5, 2.0],
+            phase: [0.0, 0.0],
+            phase_speed: [1.0, 0.0],
+            damping: 0.0,
+            amplitude: 0.5,
+            turns: 1.0,
+            segments: 2000,
+        }
+    }
+}
+
+impl Curve {
+    /// The curve frozen at animation time `time`.
+    fn at(&self, time: f32) -> CurveUniform {
+        let drift = |x: [f32; 2], speed: [f32; 2]| {
+            [x[0] + speed[0] * time, x[1] + speed[1] * time]
+        };
+        CurveUniform {
+            frequency: drift(self.frequency, self.frequency_speed),
+            phase: drift(self.phase, self.phase_speed),
+            damping: self.damping,
+            amplitude: self.amplitude,
+            turns: self.turns,
+            segments: self.segments,
+        }
+    }
+}
+
+/// `Curve` in lissajous-curve.wgsl.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, Pod, Zeroable)]
+struct CurveUniform {
+    frequency: [f32; 2],
+    phase: [f32; 2],
+    damping: f32,
+    amplitude: f32,
+    turns: f32,
+    segments: u32,
+}
+
+impl CurveUniform {
+    /// Same as `curve_point` in lissajous-curve.wgsl.
+    fn point(&self, i: u32) -> [f32; 2] {
+        let t = i as f32 / (self.segments.max(2) - 1) as f32 * TAU * self.turns;
+        let envelope = self.amplitude * (-self.damping * t).exp();
+        [
+            envelope * (self.frequency[0] * t + self.phase[0]).sin(),
+            envelope * (self.frequency[1] * t + self.phase[1]).sin(),
+        ]
+    }
+}
+
+/// Everything the arrow keys change, as saved to and loaded from TOML files. Missing keys
+/// keep their defaults.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preset {
+    pub curve: Curve,
+    /// Line color before the hue rotation
+    pub color: [f32; 4],
+    /// Rotation of the line color's hue, in degrees per second
+    pub hue_speed: f32,
+    /// In pixels
+    pub line_width: f32,
+}
+
+impl Default for Preset {
+    fn default() -> Self {
+        Self {
+            curve: Curve::default(),
+            color: COLOR,
+            hue_speed: 0.0,
+            line_width: LineStyle::default().width,
+        }
+    }
+}
+
+impl Preset {
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Ok(toml::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// The line color after `time` seconds of hue rotation.
+    fn color_at(&self, time: f32) -> [f32; 4] {
+        if self.hue_speed == 0.0 {
+            return self.color;
+        }
+        let [r, g, b, a] = self.color;
+        let hsv = Hsv::from_color(Srgb::new(r, g, b)).shift_hue(self.hue_speed * time);
+        let rgb = Srgb::from_color(hsv);
+        [rgb.red, rgb.green, rgb.blue, a]
+    }
+}
+
+/// A setting of the [`Preset`] that the arrow keys change: Up and Down select one, Left and
+/// Right step it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Control {
+    FrequencyX,
+    FrequencyY,
+    /// Of the current frequencies; stepping it makes them drift in proportion
+    Ratio,
+    FrequencyDriftX,
+    FrequencyDriftY,
+    /// Current phase of Y relative to X
+    Phase,
+    PhaseDrift,
+    Amplitude,
+    Damping,
+    Turns,
+    Segments,
+    HueSpeed,
+    LineWidth,
+}
+
+impl Control {
+    const ALL: [Control; 13] = [
+        Self::FrequencyX,
+        Self::FrequencyY,
+        Self::Ratio,
+        Self::FrequencyDriftX,
+        Self::FrequencyDriftY,
+        Self::Phase,
+        Self::PhaseDrift,
+        Self::Amplitude,
+        Self::Damping,
+        Self::Turns,
+        Self::Segments,
+        Self::HueSpeed,
+        Self::LineWidth,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::FrequencyX => "frequency x",
+            Self::FrequencyY => "frequency y",
+            Self::Ratio => "ratio y/x",
+            Self::FrequencyDriftX => "drift x /s",
+            Self::FrequencyDriftY => "drift y /s",
+            Self::Phase => "phase y-x",
+            Self::PhaseDrift => "phase drift /s",
+            Self::Amplitude => "scale",
+            Self::Damping => "damping",
+            Self::Turns => "turns",
+            Self::Segments => "segments",
+            Self::HueSpeed => "hue deg/s",
+            Self::LineWidth => "line width",
+        }
+    }
+
+    /// The setting in `preset` after `time` seconds of animation, for display.
+    fn value(self, preset: &Preset, time: f32) -> String {
+        let curve = &preset.curve;
+        let now = curve.at(time);
+        match self {
+            Self::FrequencyX => format!("{:.2}", curve.frequency[0]),
+            Self::FrequencyY => format!("{:.2}", curve.frequency[1]),
+            Self::Ratio => match ratio(&now) {
+                Some(ratio) => format!("{:.2}", ratio),
+                None => "-".into(),
+            },
+            Self::FrequencyDriftX => format!("{:.2}", curve.frequency_speed[0]),
+            Self::FrequencyDriftY => format!("{:.2}", curve.frequency_speed[1]),
+            Self::Phase => format!(
+                "{:.0}",
+                (now.phase[1] - now.phase[0]).to_degrees().rem_euclid(360.0)
+            ),
+            Self::PhaseDrift => format!("{:.2}", curve.phase_speed[0]),
+            Self::Amplitude => format!("{:.2}", curve.amplitude),
+            Self::Damping => format!("{:.2}", curve.damping),
+            Self::Turns => format!("{:.1}", curve.turns),
+            Self::Segments => curve.segments.to_string(),
+            Self::HueSpeed => format!("{:.0}", preset.hue_speed),
+            Self::LineWidth => format!("{:.1}", preset.line_width),
+        }
+    }
+
+    /// Changes the setting by `steps` steps, at `time` seconds into the animation.
+    fn step(self, preset: &mut Preset, time: f32, steps: i32) {
+        let curve = &mut preset.curve;
+        let delta = steps as f32;
+        match self {
+            Self::FrequencyX => curve.frequency[0] += 0.5 * delta,
+            Self::FrequencyY => curve.frequency[1] += 0.5 * delta,
+            Self::Ratio => {
+                // to the next quarter, kept from now on
+                let ratio = ratio(&curve.at(time)).unwrap_or(1.0);
+                let ratio = (ratio * 4.0).round() / 4.0 + 0.25 * delta;
+                curve.frequency[1] = ratio * curve.frequency[0];
+                curve.frequency_speed[1] = ratio * curve.frequency_speed[0];
+            }
+            Self::FrequencyDriftX => curve.frequency_speed[0] += 0.1 * delta,
+            Self::FrequencyDriftY => curve.frequency_speed[1] += 0.1 * delta,
+            Self::Phase => curve.phase[1] += 15_f32.to_radians() * delta,
+            Self::PhaseDrift => curve.phase_speed[0] += 0.25 * delta,
+            Self::Amplitude => curve.amplitude = (curve.amplitude + 0.05 * delta).max(0.0),
+            Self::Damping => curve.damping = (curve.damping + 0.02 * delta).max(0.0),
+            Self::Turns => curve.turns = (curve.turns + 0.5 * delta).max(0.5),
+            Self::Segments => {
+                let segments = match steps.signum() {
+                    1 => curve.segments.saturating_mul(2),
+                    -1 => curve.segments / 2,
+                    _ => curve.segments,
+                };
+                curve.segments = segments.clamp(2, MAX_SEGMENTS);
+            }
+            Self::HueSpeed => preset.hue_speed += 15.0 * delta,
+            Self::LineWidth => preset.line_width = (preset.line_width + 0.5 * delta).max(0.5),
+        }
+    }
+}
+
+/// Frequency of Y over that of X, unless X stands still.
+fn ratio(curve: &CurveUniform) -> Option<f32> {
+    let [x, y] = curve.frequency;
+    (x.abs() > 1e-3).then(|| y / x)
+}
+
+/// Where the curve's points are computed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Generator {
+    /// On the CPU, uploaded with `write_buffer` every frame
+    Cpu,
+    /// By the vertex shader of the polyline, without a point buffer
+    #[value(name = "vertex")]
+    VertexShader,
+    /// Into the point buffer by a compute pass, drawn with `draw_indirect`
+    Compute,
+}
+
+/// The polylines of the generators, for one kind of render target.
+struct Lines {
+    /// Draws from the point buffer
+    buffer: PolylineRenderer,
+    buffer_bind_group: BindGroup,
+    /// Computes the points from the curve uniform
+    generate: PolylineRenderer,
+    generate_bind_group: BindGroup,
+}
+
+impl Lines {
+    fn new(
+        device: &Device,
+        format: TextureFormat,
+        blend: BlendState,
+        uniform_buffer: &Buffer,
+        points_buffer: &Buffer,
+    ) -> Self {
+        let buffer = PolylineRenderer::new(device, format, blend);
+        let buffer_bind_group = buffer.buffer_bind_group(device, points_buffer);
+        let generate = PolylineRenderer::with_points(
+            device,
+            format,
+            blend,
+            &format!("{}{}", CURVE_WGSL, CURVE_POINTS_WGSL),
+        );
+        let generate_bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: None,
+            layout: &generate.points_bind_group_layout(),
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+        Self {
+            buffer,
+            buffer_bind_group,
+            generate,
+            generate_bind_group,
+        }
+    }
+
+    fn renderer_mut(&mut self, generator: Generator) -> &mut PolylineRenderer {
+        match generator {
+            Generator::VertexShader => &mut self.generate,
+            Generator::Cpu | Generator::Compute => &mut self.buffer,
+        }
+    }
+
+    fn draw(&self, pass: &mut RenderPass, generator: Generator, draw_args_buffer: &Buffer) {
+        match generator {
+            Generator::Cpu => self.buffer.draw(pass, &self.buffer_bind_group),
+            Generator::VertexShader => self.generate.draw(pass, &self.generate_bind_group),
+            Generator::Compute => {
+                // the segment count comes from the compute pass
+                self.buffer
+                    .draw_indirect(pass, &self.buffer_bind_group, draw_args_buffer)
+            }
+        }
+    }
+}
+
+pub struct LissajousScene {
+    curve: Curve,
+    /// See [`Preset::hue_speed`]
+    hue_speed: f32,
+    generator: Generator,
+    /// The curve at the current animation time
+    uniform: CurveUniform,
+    uniform_buffer: Buffer,
+    points_buffer: Buffer,
+    draw_args_buffer: Buffer,
+    line_style: LineStyle,
+    /// Drawing to the surface, or to the trace of `phosphor`
+    lines: Lines,
+    compute_pipeline: ComputePipeline,
+    compute_bind_group: BindGroup,
+    phosphor: Option<Phosphor>,
+    /// Settings P turns the phosphor on with
+    phosphor_settings: PhosphorSettings,
+    /// Drawn instead of the curve if set
+    audio: Option<StereoTrack>,
+    /// Points drawn in the current frame
+    point_count: u32,
+    /// Setting the arrow keys change
+    control: Control,
+    overlay: Overlay,
+    show_overlay: bool,
+    preset_path: PathBuf,
+    /// Outcome of the last save or load, shown in the overlay
+    message: Option<String>,
+    format: TextureFormat,
+    size: (u32, u32),
+    /// Animation time in seconds
+    elapsed: f32,
+}
+
+impl LissajousScene {
+    /// The default curve, generated on the CPU.
+    pub fn new(gpu: &GpuContext, format: TextureFormat, size: (u32, u32)) -> Self {
+        Self::with_curve(gpu, format, size, Curve::default(), Generator::Cpu)
+    }
+
+    pub fn with_curve(
+        gpu: &GpuContext,
+        format: TextureFormat,
+        size: (u32, u32),
+        mut curve: Curve,
+        generator: Generator,
+    ) -> Self {
+        curve.segments = curve.segments.clamp(2, MAX_SEGMENTS);
+        let device = &gpu.device;
+
+        let uniform_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Curve"),
+            size: size_of::<CurveUniform>() as u64,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let points_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Curve points"),
+            size: MAX_SEGMENTS as u64 * 2 * 4,
+            usage: BufferUsages::COPY_DST | BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+        let draw_args_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Curve draw arguments"),
+            size: size_of::<wgpu::util::DrawIndirectArgs>() as u64,
+            usage: BufferUsages::INDIRECT | BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+        let lines = Lines::new(
+            device,
+            format,
+            BlendState::ALPHA_BLENDING,
+            &uniform_buffer,
+            &points_buffer,
+        );
+        let compute_module = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("Curve generation"),
+            source: ShaderSource::Wgsl(Cow::Owned(format!(
+                "{}{}",
+                CURVE_WGSL,
+                include_str!("shaders/lissajous.wgsl")
+            ))),
+        });
+        let compute_pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
+            label: Some("Curve generation"),
+            layout: None,
+            module: &compute_module,
+            entry_point: Some("cs_generate"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+        let compute_bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: None,
+            layout: &compute_pipeline.get_bind_group_layout(0),
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: points_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: draw_args_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        Self {
+            curve,
+            hue_speed: 0.0,
+            generator,
+            uniform: curve.at(0.0),
+            uniform_buffer,
+            points_buffer,
+            draw_args_buffer,
+            line_style: LineStyle {
+                color: COLOR,
+                ..Default::default()
+            },
+            lines,
+            compute_pipeline,
+            compute_bind_group,
+            phosphor: None,
+            phosphor_settings: PhosphorSettings::default(),
+            audio: None,
+            point_count: curve.segments,
+            control: Control::ALL[0],
+            overlay: Overlay::new(device, &gpu.queue, format),
+            show_overlay: false,
+            preset_path: DEFAULT_PRESET_PATH.into(),
+            message: None,
+            format,
+            size,
+            elapsed: 0.0,
+        }
+    }
+
+    pub fn generator(&self) -> Generator {
+        self.generator
+    }
+
+    pub fn set_generator(&mut self, generator: Generator) {
+        self.generator = generator;
+    }
+
+    /// Draws the beam of `track` in sync with the animation time instead of the curve, looping
+    /// over it, or goes back to the curve with `None`. The samples are uploaded from the CPU
+    /// whatever the generator.
+    pub fn set_audio(&mut self, track: Option<StereoTrack>) {
+        self.audio = track;
+    }
+
+    /// The generator of this frame's points.
+    fn active_generator(&self) -> Generator {
+        match self.audio {
+            Some(_) => Generator::Cpu,
+            None => self.generator,
+        }
+    }
+
+    pub fn preset(&self) -> Preset {
+        Preset {
+            curve: self.curve,
+            color: self.line_style.color,
+            hue_speed: self.hue_speed,
+            line_width: self.line_style.width,
+        }
+    }
+
+    pub fn set_preset(&mut self, preset: Preset) {
+        self.curve = preset.curve;
+        self.curve.segments = self.curve.segments.clamp(2, MAX_SEGMENTS);
+        self.hue_speed = preset.hue_speed;
+        self.line_style.color = preset.color;
+        self.line_style.width = preset.line_width;
+    }
+
+    /// Where S saves the preset and L loads it from; [`DEFAULT_PRESET_PATH`] by default.
+    pub fn set_preset_path(&mut self, path: impl Into<PathBuf>) {
+        self.preset_path = path.into();
+    }
+
+    /// Shows the settings and what the keys do on top of the curve.
+    pub fn set_overlay_visible(&mut self, visible: bool) {
+        self.show_overlay = visible;
+    }
+
+    fn save_preset(&mut self) {
+        let path = self.preset_path.display();
+        let message = match self.preset().save(&self.preset_path) {
+            Ok(()) => {
+                info!("Saved the preset to {}", path);
+                format!("saved {}", path)
+            }
+            Err(e) => {
+                warn!("Can't save the preset to {}: {:?}", path, e);
+                format!("can't save {}", path)
+            }
+        };
+        self.message = Some(message);
+    }
+
+    fn load_preset(&mut self) {
+        let message = match Preset::load(&self.preset_path) {
+            Ok(preset) => {
+                self.set_preset(preset);
+                let path = self.preset_path.display();
+                info!("Loaded the preset from {}", path);
+                format!("loaded {}", path)
+            }
+            Err(e) => {
+                let path = self.preset_path.display();
+                warn!("Can't load the preset from {}: {:?}", path, e);
+                format!("can't load {}", path)
+            }
+        };
+        self.message = Some(message);
+    }
+
+    /// Queues this frame's settings panel.
+    fn queue_overlay(&mut self) {
+        let preset = self.preset();
+        let rows = Control::ALL
+            .iter()
+            .map(|&control| {
+                format!(
+                    "{:<15}{:>9}",
+                    control.name(),
+                    control.value(&preset, self.elapsed)
+                )
+            })
+            .collect::<Vec<_>>();
+        let hint = "o hides, s saves, l loads";
+        let width = rows
+            .iter()
+            .map(String::len)
+            .chain([hint.len()])
+            .chain(self.message.as_ref().map(String::len))
+            .max()
+            .unwrap_or_default() as u32
+            + 4;
+        let height = rows.len() as u32 + if self.message.is_some() { 5 } else { 4 };
+
+        let overlay = &mut self.overlay;
+        overlay.fill(0, 0, width, height, OVERLAY_BACKGROUND);
+        overlay.text(1, 1, hint, OVERLAY_HINT);
+        for (i, (row, control)) in rows.iter().zip(Control::ALL).enumerate() {
+            let y = i as u32 + 3;
+            if control == self.control {
+                overlay.fill(0, y, width, 1, OVERLAY_SELECTION);
+                overlay.text(1, y, ">", OVERLAY_TEXT);
+            }
+            overlay.text(3, y, row, OVERLAY_TEXT);
+        }
+        if let Some(message) = &self.message {
+            overlay.text(1, height - 1, message, OVERLAY_HINT);
+        }
+    }
+
+    pub fn line_style(&self) -> LineStyle {
+        self.line_style
+    }
+
+    pub fn set_line_style(&mut self, line_style: LineStyle) {
+        self.line_style = line_style;
+    }
+
+    /// Leaves a fading, glowing trail like an analog XY oscilloscope, or clears every frame with
+    /// `None`.
+    pub fn set_phosphor(&mut self, gpu: &GpuContext, settings: Option<PhosphorSettings>) {
+        let device = &gpu.device;
+        let Some(settings) = settings else {
+            self.phosphor = None;
+            self.lines = Lines::new(
+                device,
+                self.format,
+                BlendState::ALPHA_BLENDING,
+                &self.uniform_buffer,
+                &self.points_buffer,
+            );
+            return;
+        };
+        self.phosphor_settings = settings;
+        match &mut self.phosphor {
+            Some(phosphor) => phosphor.set_settings(settings),
+            None => {
+                self.phosphor = Some(Phosphor::new(device, self.format, self.size, settings));
+                self.lines = Lines::new(
+                    device,
+                    Phosphor::FORMAT,
+                    ADDITIVE_BLENDING,
+                    &self.uniform_buffer,
+                    &self.points_buffer,
+                );
+            }
+        }
+    }
+
+    /// Clears `frame.view` and draws the curve on it.
+    fn render_lines(&self, frame: &mut Frame, generator: Generator) {
+        let mut pass = frame
+            .encoder
+            .begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: None,
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: frame.view,
+                    depth_slice: None,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(Color::from_vec4d([0.3, 0.3, 0.3, 1.0])),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: frame.profiler.render_pass("Lissajous"),
+                occlusion_query_set: None,
+                multiview_mask: None,
+            });
+        self.lines
+            .draw(&mut pass, generator, &self.draw_args_buffer);
+    }
+}
+
+impl Scene for LissajousScene {
+    fn update(&mut self, gpu: &GpuContext, dt: Duration) {
+        self.elapsed += dt.as_secs_f32();
+        self.uniform = self.curve.at(self.elapsed);
+        self.point_count = self.uniform.segments;
+
+        match (&self.audio, self.generator) {
+            (Some(track), _) => {
+                // the path of the beam since the last frame
+                let end = track.looped(self.elapsed);
+                let span = if dt.is_zero() {
+                    PAUSED_AUDIO_SPAN
+                } else {
+                    dt.as_secs_f32()
+                };
+                let points = track.looped_points(end, span);
+                let points = &points[points.len().saturating_sub(MAX_SEGMENTS as usize)..];
+                if !points.is_empty() {
+                    gpu.queue
+                        .write_buffer(&self.points_buffer, 0, bytemuck::cast_slice(points));
+                }
+                self.point_count = points.len() as u32;
+            }
+            (None, Generator::Cpu) => {
+                let points = (0..self.uniform.segments)
+                    .map(|i| self.uniform.point(i))
+                    .collect::<Vec<_>>();
+                gpu.queue
+                    .write_buffer(&self.points_buffer, 0, bytemuck::cast_slice(&points));
+            }
+            (None, Generator::VertexShader | Generator::Compute) => {
+                gpu.queue
+                    .write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&self.uniform));
+            }
+        }
+
+        let mut style = self.line_style;
+        style.color = self.preset().color_at(self.elapsed);
+        if let Some(phosphor) = &mut self.phosphor {
+            phosphor.update(&gpu.queue, dt);
+            // round joints would add up to a string of bright beads
+            style.join = Join::Miter;
+            style.color[3] *= phosphor.exposure();
+        }
+        self.lines.renderer_mut(self.active_generator()).update(
+            &gpu.queue,
+            &style,
+            self.size,
+            self.point_count,
+        );
+
+        if self.show_overlay {
+            self.queue_overlay();
+        }
+        self.overlay.prepare(&gpu.queue, self.size);
+    }
+
+    fn render(&mut self, _gpu: &GpuContext, frame: &mut Frame) {
+        let generator = self.active_generator();
+        if generator == Generator::Compute {
+            let mut pass = frame.encoder.begin_compute_pass(&ComputePassDescriptor {
+                label: Some("Curve generation"),
+                timestamp_writes: frame.profiler.compute_pass("Lissajous compute"),
+            });
+            pass.set_pipeline(&self.compute_pipeline);
+            pass.set_bind_group(0, &self.compute_bind_group, &[]);
+            pass.dispatch_workgroups(self.uniform.segments.div_ceil(WORKGROUP_SIZE), 1, 1);
+        }
+
+        if let Some(phosphor) = &self.phosphor {
+            let mut pass = phosphor.begin_trace_pass(frame.encoder, frame.profiler);
+            self.lines
+                .draw(&mut pass, generator, &self.draw_args_buffer);
+            drop(pass);
+            phosphor.encode_composite(frame.encoder, frame.view, frame.profiler);
+        } else {
+            self.render_lines(frame, generator);
+        }
+        self.overlay
+            .encode_pass(frame.encoder, frame.view, frame.profiler);
+    }
+
+    /// G cycles through the generators, P turns the phosphor trail on and off and O the
+    /// settings. Up and Down select a setting and Left and Right change it; S saves them and L
+    /// loads them.
+    fn input(&mut self, gpu: &GpuContext, event: &InputEvent) -> bool {
+        match event {
+            InputEvent::KeyPressed(Key::Character('g')) => {
+                self.generator = self.generator.next();
+                true
+            }
+            InputEvent::KeyPressed(Key::Character('p')) => {
+                let settings = match self.phosphor {
+                    Some(_) => None,
+                    None => Some(self.phosphor_settings),
+                };
+                self.set_phosphor(gpu, settings);
+                true
+            }
+            InputEvent::KeyPressed(Key::Character('o')) => {
+                self.show_overlay = !self.show_overlay;
+                true
+            }
+            InputEvent::KeyPressed(Key::Character('s')) => {
+                self.save_preset();
+                true
+            }
+            InputEvent::KeyPressed(Key::Character('l')) => {
+                self.load_preset();
+                true
+            }
+            InputEvent::KeyPressed(key @ (Key::ArrowUp | Key::ArrowDown)) => {
+                let index = Control::ALL
+                    .iter()
+                    .position(|&x| x == self.control)
+                    .unwrap();
+                let count = Control::ALL.len();
+                let index = match key {
+                    Key::ArrowUp => index + count - 1,
+                    _ => index + 1,
+                };
+                self.control = Control::ALL[index % count];
+                true
+            }
+            InputEvent::KeyPressed(key @ (Key::ArrowLeft | Key::ArrowRight)) => {
+                let steps = if *key == Key::ArrowLeft { -1 } else { 1 };
+                let mut preset = self.preset();
+                self.control.step(&mut preset, self.elapsed, steps);
+                self.set_preset(preset);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn resize(&mut self, gpu: &GpuContext, size: (u32, u32)) {
+        self.size = size;
+        if let Some(phosphor) = &mut self.phosphor {
+            phosphor.resize(&gpu.device, size);
+        }
+    }
+
+    fn status(&self) -> Option<String> {
+        let source = match &self.audio {
+            Some(track) => format!(
+                "audio at {:.1} of {:.1} s",
+                track.looped(self.elapsed),
+                track.duration()
+            ),
+            None => format!("generator: {}", self.generator.name()),
+        };
+        let phosphor = if self.phosphor.is_some() {
+            ", phosphor"
+        } else {
+            ""
+        };
+        Some(format!(
+            "{} points, {}{}, {}: {}",
+            self.point_count,
+            source,
+            phosphor,
+            self.control.name(),
+            self.control.value(&self.preset(), self.elapsed)
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn preset_round_trip() {
+        let preset = Preset {
+            curve: Curve {
+                frequency: [3.0, 2.0],
+                phase: [0.25, 0.0],
+                damping: 0.1,
+                segments: 512,
+                ..Default::default()
+            },
+            color: [1.0, 0.5, 0.25, 1.0],
+            hue_speed: 30.0,
+            line_width: 4.0,
+        };
+        let path = env::temp_dir().join(format!("lissajous-test-{}.toml", std::process::id()));
+        preset.save(&path).unwrap();
+        let loaded = Preset::load(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap(), preset);
+    }
+
+    #[test]
+    fn preset_missing_keys_keep_defaults() {
+        let preset: Preset = toml::from_str("hue_speed = 10.0\n[curve]\nturns = 3.0\n").unwrap();
+        let expected = Preset {
+            curve: Curve {
+                turns: 3.0,
+                ..Default::default()
+            },
+            hue_speed: 10.0,
+            ..Default::default()
+        };
+        assert_eq!(preset, expected);
+    }
+}
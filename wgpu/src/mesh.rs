@@ -0,0 +1,374 @@
+//! Triangle meshes for the instanced renderer of [`crate::webgpu_bg`], loaded from OBJ, glTF 2.0
+//! or the text dump of interleaved floats the WebGPU Fundamentals port started with.
+//!
+//! OBJ and glTF are right-handed with counter-clockwise front faces, so their meshes are
+//! mirrored along Z into the left-handed space of the renderer, where their outside winds
+//! clockwise on screen like the legacy dump's. Missing normals are computed, smooth across the
+//! vertices the file shares between faces.
+
+use anyhow::{anyhow, bail};
+use bytemuck::{Pod, Zeroable};
+use glam::{Mat3, Mat4, Vec3};
+use log::warn;
+use std::fs;
+use std::path::Path;
+
+/// Color of surfaces without vertex colors or a material.
+const DEFAULT_COLOR: [f32; 4] = [0.8, 0.8, 0.8, 1.0];
+/// Floats per vertex of the legacy text format.
+const LEGACY_STRIDE: usize = 3 + 3 + 4;
+
+/// `VSInput` in webgpu-bg.wgsl.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct Vertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub color: [f32; 4],
+}
+
+/// A triangle list in the left-handed space of the renderer.
+#[derive(Clone, Debug, Default)]
+pub struct Mesh {
+    pub vertices: Vec<Vertex>,
+}
+
+/// Indexed triangles as a file has them, in its right-handed space.
+struct Geometry {
+    positions: Vec<Vec3>,
+    /// Empty if the file has none
+    normals: Vec<Vec3>,
+    colors: Vec<[f32; 4]>,
+    indices: Vec<u32>,
+}
+
+impl Geometry {
+    /// Appends the triangles to `vertices`, mirrored into the renderer's space.
+    fn append_to(mut self, vertices: &mut Vec<Vertex>) -> anyhow::Result<()> {
+        let count = self.positions.len();
+        if self.indices.iter().any(|&i| i as usize >= count) {
+            bail!("Vertex index out of range");
+        }
+        if self.colors.len() != count {
+            bail!(
+                "Expected {} vertex colors, got {}",
+                count,
+                self.colors.len()
+            );
+        }
+        if self.normals.len() != count {
+            self.normals = smooth_normals(&self.positions, &self.indices);
+        }
+
+        let mirror = |v: Vec3| [v.x, v.y, -v.z];
+        for triangle in self.indices.chunks_exact(3) {
+            // mirroring turns the winding around, so swapping two corners keeps the outside
+            // counter-clockwise around the normal as in the file
+            for i in [triangle[0], triangle[2], triangle[1]] {
+                let i = i as usize;
+                vertices.push(Vertex {
+                    position: mirror(self.positions[i]),
+                    normal: mirror(self.normals[i]),
+                    color: self.colors[i],
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Normals of the vertices, as the sum of the adjacent faces' normals weighted by their area.
+fn smooth_normals(positions: &[Vec3], indices: &[u32]) -> Vec<Vec3> {
+    let mut normals = vec![Vec3::ZERO; positions.len()];
+    for triangle in indices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| positions[triangle[i] as usize]);
+        let normal = (b - a).cross(c - a);
+        for &i in triangle {
+            normals[i as usize] += normal;
+        }
+    }
+    normals
+        .into_iter()
+        .map(|x| x.try_normalize().unwrap_or(Vec3::Y))
+        .collect()
+}
+
+impl Mesh {
+    /// Loads an OBJ (`.obj`), glTF (`.gltf` or `.glb`) or legacy text (`.txt`) file.
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|x| x.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let mesh = match extension.as_str() {
+            "obj" => Self::from_obj(path)?,
+            "gltf" | "glb" => Self::from_gltf(path)?,
+            "txt" => Self::parse_legacy(&fs::read_to_string(path)?)?,
+            _ => bail!("Expected an .obj, .gltf, .glb or .txt file"),
+        };
+        if mesh.vertices.is_empty() {
+            bail!("No triangles in {}", path.display());
+        }
+        Ok(mesh)
+    }
+
+    /// Loads every object of an OBJ file, colored by the diffuse color of its material.
+    pub fn from_obj(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let (models, materials) = tobj::load_obj(path.as_ref(), &tobj::GPU_LOAD_OPTIONS)?;
+        // a missing material library only costs the colors
+        let materials = materials.unwrap_or_else(|e| {
+            warn!("No materials for {}: {}", path.as_ref().display(), e);
+            Vec::new()
+        });
+
+        let mut vertices = Vec::new();
+        for model in models {
+            let mesh = model.mesh;
+            let vec3s = |x: &[f32]| x.chunks_exact(3).map(Vec3::from_slice).collect::<Vec<_>>();
+            let positions = vec3s(&mesh.positions);
+            let colors = if mesh.vertex_color.len() == mesh.positions.len() {
+                mesh.vertex_color
+                    .chunks_exact(3)
+                    .map(|x| [x[0], x[1], x[2], 1.0])
+                    .collect()
+            } else {
+                let color = mesh
+                    .material_id
+                    .and_then(|i| materials.get(i)?.diffuse)
+                    .map_or(DEFAULT_COLOR, |[r, g, b]| [r, g, b, 1.0]);
+                vec![color; positions.len()]
+            };
+            let geometry = Geometry {
+                normals: vec3s(&mesh.normals),
+                positions,
+                colors,
+                indices: mesh.indices,
+            };
+            geometry.append_to(&mut vertices)?;
+        }
+        Ok(Self { vertices })
+    }
+
+    /// Loads the triangles of the default scene of a glTF or GLB file, with the transforms of
+    /// their nodes. Vertex colors are multiplied by the base color of the material.
+    pub fn from_gltf(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let gltf::Gltf { document, blob } = gltf::Gltf::open(path)?;
+        // textures aren't used, so only the buffers are loaded
+        let buffers = gltf::import_buffers(&document, path.parent(), blob)?;
+        let scene = document
+            .default_scene()
+            .or_else(|| document.scenes().next())
+            .ok_or_else(|| anyhow!("No scene in {}", path.display()))?;
+
+        let mut vertices = Vec::new();
+        let mut nodes = scene
+            .nodes()
+            .map(|node| (node, Mat4::IDENTITY))
+            .collect::<Vec<_>>();
+        while let Some((node, parent_transform)) = nodes.pop() {
+            let transform = parent_transform * Mat4::from_cols_array_2d(&node.transform().matrix());
+            nodes.extend(node.children().map(|child| (child, transform)));
+            let Some(mesh) = node.mesh() else {
+                continue;
+            };
+            let normal_matrix = Mat3::from_mat4(transform).inverse().transpose();
+
+            for primitive in mesh.primitives() {
+                if primitive.mode() != gltf::mesh::Mode::Triangles {
+                    continue;
+                }
+                let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(|x| &**x));
+                let Some(positions) = reader.read_positions() else {
+                    continue;
+                };
+                let positions = positions
+                    .map(|x| transform.transform_point3(x.into()))
+                    .collect::<Vec<_>>();
+                let normals = reader
+                    .read_normals()
+                    .map(|normals| {
+                        normals
+                            .map(|x| (normal_matrix * Vec3::from(x)).normalize_or_zero())
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                let base_color = primitive
+                    .material()
+                    .pbr_metallic_roughness()
+                    .base_color_factor();
+                let colors = match reader.read_colors(0) {
+                    Some(colors) => colors
+                        .into_rgba_f32()
+                        .map(|x| [0, 1, 2, 3].map(|i| x[i] * base_color[i]))
+                        .collect(),
+                    None => vec![base_color; positions.len()],
+                };
+                let mut indices = match reader.read_indices() {
+                    Some(indices) => indices.into_u32().collect::<Vec<_>>(),
+                    None => (0..positions.len() as u32).collect(),
+                };
+                if transform.determinant() < 0.0 {
+                    // a mirroring transform turns the winding around
+                    for triangle in indices.chunks_exact_mut(3) {
+                        triangle.swap(1, 2);
+                    }
+                }
+                let geometry = Geometry {
+                    positions,
+                    normals,
+                    colors,
+                    indices,
+                };
+                geometry.append_to(&mut vertices)?;
+            }
+        }
+        Ok(Self { vertices })
+    }
+
+    /// Parses the legacy format of `data/webgpu-bg-data.txt`: a float per line, optionally
+    /// followed by a comma and with spaces anywhere, and position, normal and RGBA color per
+    /// vertex. It's already in the renderer's space.
+    pub fn parse_legacy(text: &str) -> anyhow::Result<Self> {
+        let floats = text
+            .lines()
+            .filter(|x| !x.trim().is_empty())
+            .map(|x| x.trim_end_matches(',').replace(' ', "").parse::<f32>())
+            .collect::<Result<Vec<_>, _>>()?;
+        if !floats.len().is_multiple_of(LEGACY_STRIDE) {
+            bail!(
+                "Expected {} floats per vertex, got {} floats",
+                LEGACY_STRIDE,
+                floats.len()
+            );
+        }
+        Ok(Self {
+            vertices: bytemuck::cast_slice(&floats).to_vec(),
+        })
+    }
+
+    /// Distance of the farthest vertex from the origin.
+    pub fn radius(&self) -> f32 {
+        self.vertices
+            .iter()
+            .map(|x| Vec3::from(x.position).length())
+            .fold(0.0, f32::max)
+    }
+
+    /// Centers the mesh on its bounding box and scales it to `radius`.
+    pub fn fit(&mut self, radius: f32) {
+        let (min, max) = self
+            .vertices
+            .iter()
+            .fold((Vec3::INFINITY, Vec3::NEG_INFINITY), |(min, max), x| {
+                (min.min(x.position.into()), max.max(x.position.into()))
+            });
+        let center = (min + max) / 2.0;
+        let extent = self
+            .vertices
+            .iter()
+            .map(|x| (Vec3::from(x.position) - center).length())
+            .fold(0.0, f32::max);
+        let scale = if extent > 0.0 { radius / extent } else { 1.0 };
+        for vertex in &mut self.vertices {
+            vertex.position = ((Vec3::from(vertex.position) - center) * scale).into();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    /// A triangle facing +Z, counter-clockwise from the front like OBJ and glTF want it.
+    const TRIANGLE_OBJ: &str = "\
+v 0 0 0
+v 1 0 0
+v 0 1 0
+f 1 2 3
+";
+
+    /// Whether the triangle winds counter-clockwise around its vertex normals, which the
+    /// left-handed projection turns into clockwise on screen.
+    fn winds_around_normal(triangle: &[Vertex]) -> bool {
+        let [a, b, c] = [0, 1, 2].map(|i| Vec3::from(triangle[i].position));
+        let face_normal = (b - a).cross(c - a);
+        triangle
+            .iter()
+            .all(|x| face_normal.dot(Vec3::from(x.normal)) > 0.0)
+    }
+
+    fn geometry(indices: Vec<u32>) -> Geometry {
+        let positions = vec![Vec3::ZERO, Vec3::X, Vec3::Y];
+        Geometry {
+            colors: vec![DEFAULT_COLOR; positions.len()],
+            normals: Vec::new(),
+            positions,
+            indices,
+        }
+    }
+
+    #[test]
+    fn parse_legacy() {
+        let text = "0,\n 1 ,\n\n2\n0\n0\n-1\n1\n0.5\n0\n1,\n";
+        let mesh = Mesh::parse_legacy(text).unwrap();
+        assert_eq!(mesh.vertices.len(), 1);
+        assert_eq!(mesh.vertices[0].position, [0.0, 1.0, 2.0]);
+        assert_eq!(mesh.vertices[0].normal, [0.0, 0.0, -1.0]);
+        assert_eq!(mesh.vertices[0].color, [1.0, 0.5, 0.0, 1.0]);
+
+        assert!(Mesh::parse_legacy(&format!("{}1\n", text)).is_err());
+        assert!(Mesh::parse_legacy("x\n").is_err());
+    }
+
+    #[test]
+    fn smooth_normals_average_adjacent_faces() {
+        // two faces meeting at a right angle along the X axis
+        let positions = [Vec3::ZERO, Vec3::X, Vec3::Y, Vec3::NEG_Z];
+        let normals = smooth_normals(&positions, &[0, 1, 2, 0, 1, 3]);
+        assert!(normals[2].abs_diff_eq(Vec3::Z, 1e-6));
+        assert!(normals[3].abs_diff_eq(Vec3::Y, 1e-6));
+        let shared = Vec3::new(0.0, 1.0, 1.0).normalize();
+        assert!(normals[0].abs_diff_eq(shared, 1e-6));
+        assert!(normals[1].abs_diff_eq(shared, 1e-6));
+    }
+
+    #[test]
+    fn append_to_checks_indices() {
+        let mut vertices = Vec::new();
+        assert!(geometry(vec![0, 1, 3]).append_to(&mut vertices).is_err());
+        assert!(vertices.is_empty());
+    }
+
+    #[test]
+    fn append_to_mirrors_and_keeps_winding() {
+        let mut vertices = Vec::new();
+        geometry(vec![0, 1, 2]).append_to(&mut vertices).unwrap();
+        let positions = vertices.iter().map(|x| x.position).collect::<Vec<_>>();
+        assert_eq!(
+            positions,
+            [[0.0, 0.0, 0.0], [0.0, 1.0, 0.0], [1.0, 0.0, 0.0]]
+        );
+        assert!(vertices.iter().all(|x| x.normal == [0.0, 0.0, -1.0]));
+        assert!(winds_around_normal(&vertices));
+    }
+
+    #[test]
+    fn obj_winds_like_legacy_dump() {
+        let path = env::temp_dir().join(format!("webgpu-bg-mesh-test-{}.obj", std::process::id()));
+        fs::write(&path, TRIANGLE_OBJ).unwrap();
+        let mesh = Mesh::load(&path);
+        fs::remove_file(&path).unwrap();
+        let mesh = mesh.unwrap();
+
+        assert_eq!(mesh.vertices.len(), 3);
+        assert!(mesh.vertices.iter().all(|x| x.color == DEFAULT_COLOR));
+        assert!(winds_around_normal(&mesh.vertices));
+
+        let legacy = crate::webgpu_bg::default_mesh();
+        assert!(legacy.vertices.chunks_exact(3).all(winds_around_normal));
+    }
+}
@@ -0,0 +1,154 @@
+//! Renders [`Scene`]s to numbered PNG files instead of a window, e.g. to turn them into a video
+//! or to try them on a machine without a display.
+
+use crate::profiler::GpuProfiler;
+use crate::scene::{Frame, GpuContext, Scene};
+use crate::time_source::TimeSource;
+use crate::wgpu_instance_with_env_backend;
+use anyhow::anyhow;
+use log::info;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::sync::oneshot;
+use wgpu::wgt::PollType;
+use wgpu::TextureFormat;
+
+/// What [`Frame::view`] is, like the sRGB views of a surface.
+const FORMAT: TextureFormat = TextureFormat::Rgba8UnormSrgb;
+const BYTES_PER_PIXEL: u32 = 4;
+
+pub struct OfflineConfig {
+    pub size: (u32, u32),
+    pub frames: u32,
+    /// Frames per second of animation time
+    pub fps: f32,
+    /// Where `frame-00000.png` and so on go; created if missing
+    pub output_dir: PathBuf,
+}
+
+/// Renders `config.frames` frames of `init`'s scene with a fixed time step of `1 / config.fps`,
+/// starting at time 0.
+pub fn render_frames<S, F>(config: &OfflineConfig, init: F) -> anyhow::Result<()>
+where
+    S: Scene,
+    F: FnOnce(&GpuContext, TextureFormat, (u32, u32)) -> S,
+{
+    pollster::block_on(render_frames_async(config, init))
+}
+
+async fn render_frames_async<S, F>(config: &OfflineConfig, init: F) -> anyhow::Result<()>
+where
+    S: Scene,
+    F: FnOnce(&GpuContext, TextureFormat, (u32, u32)) -> S,
+{
+    let (width, height) = config.size;
+    if width == 0 || height == 0 {
+        return Err(anyhow!("Frame size must be non-zero"));
+    }
+    if !(config.fps > 0.0 && config.fps.is_finite()) {
+        return Err(anyhow!("Frame rate must be positive, got {}", config.fps));
+    }
+    fs::create_dir_all(&config.output_dir)?;
+
+    let gpu = GpuContext::new(wgpu_instance_with_env_backend(), None).await?;
+    info!("Adapter: {:?}", gpu.adapter.get_info());
+    let device = &gpu.device;
+    let mut profiler = GpuProfiler::new(device, &gpu.queue);
+    let mut scene = init(&gpu, FORMAT, config.size);
+
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Offline Frame"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&Default::default());
+    let padded_bytes_per_row =
+        (width * BYTES_PER_PIXEL).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+    let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Offline Readback"),
+        size: padded_bytes_per_row as u64 * height as u64,
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    let mut time_source = TimeSource::fixed(1.0 / config.fps);
+    let mut last_time = None;
+    let mut pixels = vec![0_u8; (width * height * BYTES_PER_PIXEL) as usize];
+    for index in 0..config.frames {
+        // like SceneHost, the first frame shows time 0
+        let time = time_source.next_frame();
+        let dt = Duration::from_secs_f32(time - last_time.replace(time).unwrap_or(time));
+        scene.update(&gpu, dt);
+
+        let mut encoder = device.create_command_encoder(&Default::default());
+        let mut frame = Frame {
+            encoder: &mut encoder,
+            view: &view,
+            size: config.size,
+            profiler: &mut profiler,
+        };
+        scene.render(&gpu, &mut frame);
+        profiler.resolve(&mut encoder);
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &readback_buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: None,
+                },
+            },
+            texture.size(),
+        );
+        gpu.queue.submit([encoder.finish()]);
+        profiler.end_frame();
+
+        let (tx, rx) = oneshot::channel();
+        readback_buffer.map_async(wgpu::MapMode::Read, .., |e| {
+            tx.send(e).unwrap();
+        });
+        device.poll(PollType::Wait {
+            submission_index: None,
+            timeout: None,
+        })?;
+        rx.await??;
+        {
+            let mapped = readback_buffer.get_mapped_range(..);
+            let row_bytes = (width * BYTES_PER_PIXEL) as usize;
+            for (dst, src) in pixels
+                .chunks_exact_mut(row_bytes)
+                .zip(mapped.chunks_exact(padded_bytes_per_row as usize))
+            {
+                dst.copy_from_slice(&src[..row_bytes]);
+            }
+        }
+        readback_buffer.unmap();
+
+        let path = config.output_dir.join(format!("frame-{:05}.png", index));
+        let mut encoder = png::Encoder::new(BufWriter::new(File::create(&path)?), width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+        encoder.write_header()?.write_image_data(&pixels)?;
+
+        if (index + 1) % 60 == 0 || index + 1 == config.frames {
+            info!("Rendered {} of {} frames", index + 1, config.frames);
+        }
+    }
+    if let Some(status) = scene.status() {
+        info!("{}", status);
+    }
+    Ok(())
+}
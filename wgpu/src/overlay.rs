@@ -0,0 +1,325 @@
+//! Immediate-mode text drawn on top of a scene, for settings and readouts that don't fit in
+//! the window title.
+//!
+//! Every frame, queue text and boxes with [`Overlay::text`] and [`Overlay::fill`], upload
+//! them with [`Overlay::prepare`] and draw them with [`Overlay::encode_pass`]. Positions are
+//! in character cells of a built-in 5×7 pixel font, scaled by whole pixels to suit the
+//! viewport. The font only has upper-case letters; lower case is shown as upper case.
+
+use crate::profiler::GpuProfiler;
+use bytemuck::{Pod, Zeroable};
+use wgpu::util::{DeviceExt, TextureDataOrder};
+use wgpu::{
+    include_wgsl, BindGroup, BindGroupDescriptor, BindGroupEntry, BindingResource, BlendState,
+    Buffer, BufferDescriptor, BufferUsages, ColorTargetState, CommandEncoder, Device,
+    FragmentState, PrimitiveState, PrimitiveTopology, Queue, RenderPipeline,
+    RenderPipelineDescriptor, TextureFormat, TextureView, VertexBufferLayout, VertexState,
+    VertexStepMode,
+};
+
+/// Texels of a character cell in the font texture: the glyph and a column and row of spacing.
+/// Must match `GLYPH_CELL` in overlay.wgsl.
+const GLYPH_CELL: (u32, u32) = (6, 8);
+/// Instances drawn at most per frame; more are dropped.
+const MAX_INSTANCES: usize = 4096;
+/// Viewport height the font is shown at 1:1 for; it's scaled up by whole pixels above that.
+const UNSCALED_HEIGHT: u32 = 360;
+/// Cell 0 of the font texture, filled completely.
+const SOLID: u32 = 0;
+
+/// Rows of the glyphs from the top, the leftmost pixel in bit 4.
+#[rustfmt::skip]
+const GLYPHS: [(char, [u8; 7]); 53] = [
+    ('A', [0b01110, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001]),
+    ('B', [0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110]),
+    ('C', [0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110]),
+    ('D', [0b11110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b11110]),
+    ('E', [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111]),
+    ('F', [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000]),
+    ('G', [0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111]),
+    ('H', [0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001]),
+    ('I', [0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110]),
+    ('J', [0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100]),
+    ('K', [0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001]),
+    ('L', [0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111]),
+    ('M', [0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001]),
+    ('N', [0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001]),
+    ('O', [0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110]),
+    ('P', [0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000]),
+    ('Q', [0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101]),
+    ('R', [0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001]),
+    ('S', [0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110]),
+    ('T', [0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100]),
+    ('U', [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110]),
+    ('V', [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100]),
+    ('W', [0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010]),
+    ('X', [0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001]),
+    ('Y', [0b10001, 0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100]),
+    ('Z', [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111]),
+    ('0', [0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110]),
+    ('1', [0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110]),
+    ('2', [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111]),
+    ('3', [0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110]),
+    ('4', [0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010]),
+    ('5', [0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110]),
+    ('6', [0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110]),
+    ('7', [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000]),
+    ('8', [0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110]),
+    ('9', [0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100]),
+    ('.', [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100]),
+    (',', [0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b00100, 0b01000]),
+    (':', [0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b01100, 0b00000]),
+    ('-', [0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000]),
+    ('+', [0b00000, 0b00100, 0b00100, 0b11111, 0b00100, 0b00100, 0b00000]),
+    ('=', [0b00000, 0b00000, 0b11111, 0b00000, 0b11111, 0b00000, 0b00000]),
+    ('*', [0b00000, 0b00100, 0b10101, 0b01110, 0b10101, 0b00100, 0b00000]),
+    ('/', [0b00000, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b00000]),
+    ('%', [0b11000, 0b11001, 0b00010, 0b00100, 0b01000, 0b10011, 0b00011]),
+    ('(', [0b00010, 0b00100, 0b01000, 0b01000, 0b01000, 0b00100, 0b00010]),
+    (')', [0b01000, 0b00100, 0b00010, 0b00010, 0b00010, 0b00100, 0b01000]),
+    ('[', [0b01110, 0b01000, 0b01000, 0b01000, 0b01000, 0b01000, 0b01110]),
+    (']', [0b01110, 0b00010, 0b00010, 0b00010, 0b00010, 0b00010, 0b01110]),
+    ('<', [0b00010, 0b00100, 0b01000, 0b10000, 0b01000, 0b00100, 0b00010]),
+    ('>', [0b01000, 0b00100, 0b00010, 0b00001, 0b00010, 0b00100, 0b01000]),
+    ('_', [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b11111]),
+    ('?', [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b00000, 0b00100]),
+];
+
+/// `Params` in overlay.wgsl.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, Pod, Zeroable)]
+struct Params {
+    viewport: [f32; 2],
+    cell: [f32; 2],
+}
+
+/// `Instance` in overlay.wgsl.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, Pod, Zeroable)]
+struct Instance {
+    position: [f32; 2],
+    size: [f32; 2],
+    color: [f32; 4],
+    glyph: u32,
+}
+
+/// Cell of `c` in the font texture, or `None` for blanks.
+fn glyph(c: char) -> Option<u32> {
+    if c.is_whitespace() {
+        return None;
+    }
+    let c = c.to_ascii_uppercase();
+    let index = GLYPHS
+        .iter()
+        .position(|&(x, _)| x == c)
+        .or_else(|| GLYPHS.iter().position(|&(x, _)| x == '?'))?;
+    Some(index as u32 + 1)
+}
+
+/// Texels of the font texture: [`SOLID`], then [`GLYPHS`] in a row.
+fn font_texels() -> Vec<u8> {
+    let (cell_width, cell_height) = GLYPH_CELL;
+    let width = (GLYPHS.len() as u32 + 1) * cell_width;
+    let mut texels = vec![0_u8; (width * cell_height) as usize];
+    for y in 0..cell_height {
+        let row = &mut texels[(y * width) as usize..][..width as usize];
+        row[..cell_width as usize].fill(255);
+        for (index, (_, rows)) in GLYPHS.iter().enumerate() {
+            let Some(&bits) = rows.get(y as usize) else {
+                continue;
+            };
+            let cell = &mut row[((index as u32 + 1) * cell_width) as usize..];
+            for (x, texel) in cell[..5].iter_mut().enumerate() {
+                if bits & (0b10000 >> x) != 0 {
+                    *texel = 255;
+                }
+            }
+        }
+    }
+    texels
+}
+
+pub struct Overlay {
+    pipeline: RenderPipeline,
+    bind_group: BindGroup,
+    params_buffer: Buffer,
+    instance_buffer: Buffer,
+    /// Queued since the last `prepare`
+    instances: Vec<Instance>,
+    /// Uploaded by the last `prepare`
+    instance_count: u32,
+}
+
+impl Overlay {
+    /// Draws on views of `format`.
+    pub fn new(device: &Device, queue: &Queue, format: TextureFormat) -> Self {
+        let module = device.create_shader_module(include_wgsl!("shaders/overlay.wgsl"));
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Overlay"),
+            layout: None,
+            vertex: VertexState {
+                module: &module,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[VertexBufferLayout {
+                    array_stride: size_of::<Instance>() as u64,
+                    step_mode: VertexStepMode::Instance,
+                    attributes: &wgpu::vertex_attr_array![
+                        0 => Float32x2, 1 => Float32x2, 2 => Float32x4, 3 => Uint32
+                    ],
+                }],
+            },
+            fragment: Some(FragmentState {
+                module: &module,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(ColorTargetState {
+                    format,
+                    blend: Some(BlendState::ALPHA_BLENDING),
+                    write_mask: Default::default(),
+                })],
+            }),
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleStrip,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: Default::default(),
+            multiview_mask: None,
+            cache: None,
+        });
+
+        let font = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some("Overlay font"),
+                size: wgpu::Extent3d {
+                    width: (GLYPHS.len() as u32 + 1) * GLYPH_CELL.0,
+                    height: GLYPH_CELL.1,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: TextureFormat::R8Unorm,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+            TextureDataOrder::LayerMajor,
+            &font_texels(),
+        );
+        let params_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Overlay params"),
+            size: size_of::<Params>() as u64,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let instance_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Overlay instances"),
+            size: (MAX_INSTANCES * size_of::<Instance>()) as u64,
+            usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Overlay"),
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: params_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::TextureView(&font.create_view(&Default::default())),
+                },
+            ],
+        });
+
+        Self {
+            pipeline,
+            bind_group,
+            params_buffer,
+            instance_buffer,
+            instances: Vec::new(),
+            instance_count: 0,
+        }
+    }
+
+    /// Queues a line of `text` starting at the cell in `column` and `row`.
+    pub fn text(&mut self, column: u32, row: u32, text: &str, color: [f32; 4]) {
+        for (i, c) in text.chars().enumerate() {
+            if let Some(glyph) = glyph(c) {
+                self.instances.push(Instance {
+                    position: [(column as usize + i) as f32, row as f32],
+                    size: [1.0, 1.0],
+                    color,
+                    glyph,
+                });
+            }
+        }
+    }
+
+    /// Queues a box covering `columns` × `rows` cells from `column` and `row`, e.g. as the
+    /// background of text queued after it.
+    pub fn fill(&mut self, column: u32, row: u32, columns: u32, rows: u32, color: [f32; 4]) {
+        self.instances.push(Instance {
+            position: [column as f32, row as f32],
+            size: [columns as f32, rows as f32],
+            color,
+            glyph: SOLID,
+        });
+    }
+
+    /// Uploads what was queued since the last call for views of `size`, and starts over.
+    pub fn prepare(&mut self, queue: &Queue, size: (u32, u32)) {
+        let scale = (size.1 / UNSCALED_HEIGHT).max(1) as f32;
+        let params = Params {
+            viewport: [size.0.max(1) as f32, size.1.max(1) as f32],
+            cell: [GLYPH_CELL.0 as f32 * scale, GLYPH_CELL.1 as f32 * scale],
+        };
+        queue.write_buffer(&self.params_buffer, 0, bytemuck::bytes_of(&params));
+
+        self.instances.truncate(MAX_INSTANCES);
+        if !self.instances.is_empty() {
+            queue.write_buffer(
+                &self.instance_buffer,
+                0,
+                bytemuck::cast_slice(&self.instances),
+            );
+        }
+        self.instance_count = self.instances.len() as u32;
+        self.instances.clear();
+    }
+
+    /// Records a pass drawing the prepared text over `target`, unless there is none.
+    pub fn encode_pass(
+        &self,
+        encoder: &mut CommandEncoder,
+        target: &TextureView,
+        profiler: &mut GpuProfiler,
+    ) {
+        if self.instance_count == 0 {
+            return;
+        }
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Overlay"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                depth_slice: None,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: profiler.render_pass("Overlay"),
+            occlusion_query_set: None,
+            multiview_mask: None,
+        });
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &self.bind_group, &[]);
+        pass.set_vertex_buffer(0, self.instance_buffer.slice(..));
+        pass.draw(0..4, 0..self.instance_count);
+    }
+}
@@ -0,0 +1,454 @@
+//! Persistence of an analog oscilloscope's screen, for curves that should leave a trail.
+//!
+//! Instead of being cleared, the trace lives in an HDR texture that fades a little every
+//! frame; new strokes are added on top of it with [`ADDITIVE_BLENDING`]. Showing it adds a
+//! blurred copy, which makes the bright parts glow.
+
+use crate::profiler::GpuProfiler;
+use bytemuck::{Pod, Zeroable};
+use std::time::Duration;
+use wgpu::{
+    include_wgsl, BindGroup, BindGroupDescriptor, BindGroupEntry, BindingResource, BlendComponent,
+    BlendFactor, BlendOperation, BlendState, Buffer, BufferDescriptor, BufferUsages, Color,
+    ColorTargetState, CommandEncoder, Device, FilterMode, FragmentState, Queue, RenderPass,
+    RenderPipeline, RenderPipelineDescriptor, Sampler, SamplerDescriptor, ShaderModule,
+    TextureFormat, TextureView, VertexState,
+};
+
+/// Adds the color, weighted by its alpha, to the trace.
+pub const ADDITIVE_BLENDING: BlendState = BlendState {
+    color: BlendComponent {
+        src_factor: BlendFactor::SrcAlpha,
+        dst_factor: BlendFactor::One,
+        operation: BlendOperation::Add,
+    },
+    alpha: BlendComponent {
+        src_factor: BlendFactor::One,
+        dst_factor: BlendFactor::One,
+        operation: BlendOperation::Add,
+    },
+};
+
+/// Multiplies the trace by the blend constant.
+const DECAY_BLENDING: BlendState = BlendState {
+    color: BlendComponent {
+        src_factor: BlendFactor::Zero,
+        dst_factor: BlendFactor::Constant,
+        operation: BlendOperation::Add,
+    },
+    alpha: BlendComponent {
+        src_factor: BlendFactor::Zero,
+        dst_factor: BlendFactor::Constant,
+        operation: BlendOperation::Add,
+    },
+};
+
+#[derive(Copy, Clone, Debug)]
+pub struct PhosphorSettings {
+    /// How fast the trace fades, as the exponential decay rate of its brightness per second.
+    /// Must be positive.
+    pub decay_rate: f32,
+    /// Standard deviation of the glow, in pixels. Must not be negative.
+    pub glow_radius: f32,
+    /// Brightness of the glow relative to the trace; 0 turns it off. Must not be negative.
+    pub glow_strength: f32,
+}
+
+impl PhosphorSettings {
+    fn assert_valid(&self) {
+        assert!(
+            self.decay_rate > 0.0 && self.decay_rate.is_finite(),
+            "Invalid decay rate: {}",
+            self.decay_rate
+        );
+        assert!(
+            self.glow_radius >= 0.0 && self.glow_radius.is_finite(),
+            "Invalid glow radius: {}",
+            self.glow_radius
+        );
+        assert!(
+            self.glow_strength >= 0.0 && self.glow_strength.is_finite(),
+            "Invalid glow strength: {}",
+            self.glow_strength
+        );
+    }
+}
+
+impl Default for PhosphorSettings {
+    fn default() -> Self {
+        Self {
+            decay_rate: 4.0,
+            glow_radius: 6.0,
+            glow_strength: 1.5,
+        }
+    }
+}
+
+/// `Blur` in phosphor.wgsl.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, Pod, Zeroable)]
+struct BlurUniform {
+    step: [f32; 2],
+}
+
+/// `Composite` in phosphor.wgsl.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, Pod, Zeroable)]
+struct CompositeUniform {
+    glow_strength: f32,
+}
+
+/// The trace and the glow textures, which depend on the size of the output.
+struct Targets {
+    trace: TextureView,
+    /// Half resolution; blurred horizontally, then vertically
+    glow: [TextureView; 2],
+    blur_bind_groups: [BindGroup; 2],
+    composite_bind_group: BindGroup,
+    size: (u32, u32),
+}
+
+pub struct Phosphor {
+    settings: PhosphorSettings,
+    decay_pipeline: RenderPipeline,
+    blur_pipeline: RenderPipeline,
+    composite_pipeline: RenderPipeline,
+    sampler: Sampler,
+    blur_buffers: [Buffer; 2],
+    composite_buffer: Buffer,
+    targets: Targets,
+    /// Factor the trace is multiplied by in the next frame
+    fade: f32,
+}
+
+impl Phosphor {
+    /// Format of the trace that strokes are drawn to.
+    pub const FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+
+    /// Shows the trace on views of `output_format` and `size`.
+    pub fn new(
+        device: &Device,
+        output_format: TextureFormat,
+        size: (u32, u32),
+        settings: PhosphorSettings,
+    ) -> Self {
+        settings.assert_valid();
+        let module = device.create_shader_module(include_wgsl!("shaders/phosphor.wgsl"));
+        let decay_pipeline =
+            Self::create_pipeline(device, &module, "fs_decay", Self::FORMAT, DECAY_BLENDING);
+        let blur_pipeline = Self::create_pipeline(
+            device,
+            &module,
+            "fs_blur",
+            Self::FORMAT,
+            BlendState::REPLACE,
+        );
+        let composite_pipeline = Self::create_pipeline(
+            device,
+            &module,
+            "fs_composite",
+            output_format,
+            BlendState::REPLACE,
+        );
+
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            label: Some("Phosphor Sampler"),
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..Default::default()
+        });
+        let create_buffer = |label, size: usize| {
+            device.create_buffer(&BufferDescriptor {
+                label: Some(label),
+                size: size as u64,
+                usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            })
+        };
+        let blur_buffers = [
+            create_buffer("Phosphor blur", size_of::<BlurUniform>()),
+            create_buffer("Phosphor blur", size_of::<BlurUniform>()),
+        ];
+        let composite_buffer = create_buffer("Phosphor composite", size_of::<CompositeUniform>());
+
+        let targets = Self::create_targets(
+            device,
+            &blur_pipeline,
+            &composite_pipeline,
+            &sampler,
+            &blur_buffers,
+            &composite_buffer,
+            size,
+        );
+        Self {
+            settings,
+            decay_pipeline,
+            blur_pipeline,
+            composite_pipeline,
+            sampler,
+            blur_buffers,
+            composite_buffer,
+            targets,
+            fade: 1.0,
+        }
+    }
+
+    fn create_pipeline(
+        device: &Device,
+        module: &ShaderModule,
+        fragment_entry_point: &str,
+        format: TextureFormat,
+        blend: BlendState,
+    ) -> RenderPipeline {
+        device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some(fragment_entry_point),
+            layout: None,
+            vertex: VertexState {
+                module,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(FragmentState {
+                module,
+                entry_point: Some(fragment_entry_point),
+                compilation_options: Default::default(),
+                targets: &[Some(ColorTargetState {
+                    format,
+                    blend: Some(blend),
+                    write_mask: Default::default(),
+                })],
+            }),
+            primitive: Default::default(),
+            depth_stencil: None,
+            multisample: Default::default(),
+            multiview_mask: None,
+            cache: None,
+        })
+    }
+
+    fn create_targets(
+        device: &Device,
+        blur_pipeline: &RenderPipeline,
+        composite_pipeline: &RenderPipeline,
+        sampler: &Sampler,
+        blur_buffers: &[Buffer; 2],
+        composite_buffer: &Buffer,
+        size: (u32, u32),
+    ) -> Targets {
+        let create_view = |label, (width, height): (u32, u32)| {
+            device
+                .create_texture(&wgpu::TextureDescriptor {
+                    label: Some(label),
+                    size: wgpu::Extent3d {
+                        width: width.max(1),
+                        height: height.max(1),
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: Self::FORMAT,
+                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                        | wgpu::TextureUsages::TEXTURE_BINDING,
+                    view_formats: &[],
+                })
+                .create_view(&Default::default())
+        };
+        let trace = create_view("Phosphor trace", size);
+        let glow_size = (size.0 / 2, size.1 / 2);
+        let glow = [
+            create_view("Phosphor glow", glow_size),
+            create_view("Phosphor glow", glow_size),
+        ];
+
+        let blur_bind_group = |source: &TextureView, uniform: &Buffer| {
+            device.create_bind_group(&BindGroupDescriptor {
+                label: Some("Phosphor blur"),
+                layout: &blur_pipeline.get_bind_group_layout(0),
+                entries: &[
+                    BindGroupEntry {
+                        binding: 0,
+                        resource: BindingResource::TextureView(source),
+                    },
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: BindingResource::Sampler(sampler),
+                    },
+                    BindGroupEntry {
+                        binding: 2,
+                        resource: uniform.as_entire_binding(),
+                    },
+                ],
+            })
+        };
+        let blur_bind_groups = [
+            blur_bind_group(&trace, &blur_buffers[0]),
+            blur_bind_group(&glow[0], &blur_buffers[1]),
+        ];
+        let composite_bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Phosphor composite"),
+            layout: &composite_pipeline.get_bind_group_layout(0),
+            entries: &[
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Sampler(sampler),
+                },
+                BindGroupEntry {
+                    binding: 3,
+                    resource: BindingResource::TextureView(&trace),
+                },
+                BindGroupEntry {
+                    binding: 4,
+                    resource: BindingResource::TextureView(&glow[1]),
+                },
+                BindGroupEntry {
+                    binding: 5,
+                    resource: composite_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        Targets {
+            trace,
+            glow,
+            blur_bind_groups,
+            composite_bind_group,
+            size,
+        }
+    }
+
+    pub fn settings(&self) -> PhosphorSettings {
+        self.settings
+    }
+
+    pub fn set_settings(&mut self, settings: PhosphorSettings) {
+        settings.assert_valid();
+        self.settings = settings;
+    }
+
+    /// Follows the size of the output. Clears the trace if it changes.
+    pub fn resize(&mut self, device: &Device, size: (u32, u32)) {
+        if size != self.targets.size {
+            self.targets = Self::create_targets(
+                device,
+                &self.blur_pipeline,
+                &self.composite_pipeline,
+                &self.sampler,
+                &self.blur_buffers,
+                &self.composite_buffer,
+                size,
+            );
+        }
+    }
+
+    /// Lets `dt` pass for the next frame: the trace fades by as much, and strokes drawn in
+    /// it should be weighted by [`Self::exposure`].
+    pub fn update(&mut self, queue: &Queue, dt: Duration) {
+        self.fade = (-self.settings.decay_rate * dt.as_secs_f32()).exp();
+
+        let size = self.targets.size;
+        // taps half a standard deviation apart
+        let step = self.settings.glow_radius / 2.0;
+        let steps = [
+            [step / size.0.max(1) as f32, 0.0],
+            [0.0, step / size.1.max(1) as f32],
+        ];
+        for (buffer, step) in self.blur_buffers.iter().zip(steps) {
+            queue.write_buffer(buffer, 0, bytemuck::bytes_of(&BlurUniform { step }));
+        }
+        let composite = CompositeUniform {
+            glow_strength: self.settings.glow_strength,
+        };
+        queue.write_buffer(&self.composite_buffer, 0, bytemuck::bytes_of(&composite));
+    }
+
+    /// Alpha for the strokes of this frame. A curve that stands still settles at its color,
+    /// whatever the frame rate; while paused, nothing is added.
+    pub fn exposure(&self) -> f32 {
+        1.0 - self.fade
+    }
+
+    /// Begins a pass on the trace that has already faded. Draw the strokes of this frame into
+    /// it, with pipelines for [`Self::FORMAT`] and [`ADDITIVE_BLENDING`].
+    pub fn begin_trace_pass<'a>(
+        &self,
+        encoder: &'a mut CommandEncoder,
+        profiler: &mut GpuProfiler,
+    ) -> RenderPass<'a> {
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Phosphor trace"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &self.targets.trace,
+                depth_slice: None,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: profiler.render_pass("Phosphor trace"),
+            occlusion_query_set: None,
+            multiview_mask: None,
+        });
+        let fade = self.fade as f64;
+        pass.set_blend_constant(Color {
+            r: fade,
+            g: fade,
+            b: fade,
+            a: fade,
+        });
+        pass.set_pipeline(&self.decay_pipeline);
+        pass.draw(0..3, 0..1);
+        pass
+    }
+
+    /// Records the passes that blur the trace and show it with its glow on `target`.
+    pub fn encode_composite(
+        &self,
+        encoder: &mut CommandEncoder,
+        target: &TextureView,
+        profiler: &mut GpuProfiler,
+    ) {
+        let passes = [
+            ("Phosphor blur H", &self.targets.glow[0]),
+            ("Phosphor blur V", &self.targets.glow[1]),
+        ];
+        for ((label, view), bind_group) in passes.into_iter().zip(&self.targets.blur_bind_groups) {
+            let mut pass = Self::begin_fullscreen_pass(encoder, label, view, profiler);
+            pass.set_pipeline(&self.blur_pipeline);
+            pass.set_bind_group(0, bind_group, &[]);
+            pass.draw(0..3, 0..1);
+        }
+
+        let mut pass = Self::begin_fullscreen_pass(encoder, "Phosphor composite", target, profiler);
+        pass.set_pipeline(&self.composite_pipeline);
+        pass.set_bind_group(0, &self.targets.composite_bind_group, &[]);
+        pass.draw(0..3, 0..1);
+    }
+
+    fn begin_fullscreen_pass<'a>(
+        encoder: &'a mut CommandEncoder,
+        label: &'static str,
+        view: &TextureView,
+        profiler: &mut GpuProfiler,
+    ) -> RenderPass<'a> {
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some(label),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                depth_slice: None,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: profiler.render_pass(label),
+            occlusion_query_set: None,
+            multiview_mask: None,
+        })
+    }
+}
@@ -0,0 +1,221 @@
+//! Thick anti-aliased lines through a list of points, for plots and curves.
+//!
+//! `LineStrip` topology only draws aliased lines one pixel wide. [`PolylineRenderer`] expands
+//! every segment into a quad in the vertex shader instead, so the width is in pixels whatever
+//! the resolution, and fades the edges out over a pixel in the fragment shader.
+//!
+//! The points can come from a storage buffer ([`PolylineRenderer::new`]) or be computed by WGSL
+//! of the caller's ([`PolylineRenderer::with_points`]).
+
+use bytemuck::{Pod, Zeroable};
+use std::borrow::Cow;
+use std::str::FromStr;
+use wgpu::{
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BlendState, Buffer,
+    BufferDescriptor, BufferUsages, ColorTargetState, Device, FragmentState, Queue, RenderPass,
+    RenderPipeline, RenderPipelineDescriptor, ShaderModuleDescriptor, ShaderSource, TextureFormat,
+    VertexState,
+};
+
+/// Points from `@group(1) @binding(0)`, a storage buffer of `vec2f`s in clip space.
+pub const BUFFER_POINTS: &str = "
+@group(1) @binding(0) var<storage, read> points: array<vec2f>;
+
+fn polyline_point(i: u32) -> vec2f {
+    return points[i];
+}
+";
+
+/// How consecutive segments are joined. The values match `JOIN_*` in polyline.wgsl.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Join {
+    /// Sharp corners, cut short beyond four times the width. The ends are butt.
+    Miter = 0,
+    /// Rounded corners and ends. Segments overlap at the joints, which shows with translucent
+    /// colors and additive blending.
+    Round = 1,
+}
+
+/// Parses `miter` and `round`.
+impl FromStr for Join {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "miter" => Ok(Self::Miter),
+            "round" => Ok(Self::Round),
+            _ => Err("expected `miter` or `round`".into()),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct LineStyle {
+    /// In physical pixels
+    pub width: f32,
+    pub join: Join,
+    /// Linear RGBA
+    pub color: [f32; 4],
+}
+
+impl Default for LineStyle {
+    fn default() -> Self {
+        Self {
+            width: 2.0,
+            join: Join::Round,
+            color: [1.0; 4],
+        }
+    }
+}
+
+/// `Params` in polyline.wgsl.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, Pod, Zeroable)]
+struct Params {
+    color: [f32; 4],
+    viewport: [f32; 2],
+    half_width: f32,
+    join: u32,
+    point_count: u32,
+    _pad: [u32; 3],
+}
+
+/// Draws one polyline style; use a renderer per style to draw lines that look different in the
+/// same frame.
+pub struct PolylineRenderer {
+    pipeline: RenderPipeline,
+    params_buffer: Buffer,
+    params_bind_group: BindGroup,
+    point_count: u32,
+}
+
+impl PolylineRenderer {
+    /// Lines through the points of a storage buffer; see [`Self::buffer_bind_group`]. The edges
+    /// fade out through the alpha of the color, so `blend` should weight it by alpha, like
+    /// [`BlendState::ALPHA_BLENDING`] does.
+    pub fn new(device: &Device, format: TextureFormat, blend: BlendState) -> Self {
+        Self::with_points(device, format, blend, BUFFER_POINTS)
+    }
+
+    /// Lines through the points `points_wgsl` computes. It has to define
+    /// `fn polyline_point(i: u32) -> vec2f` returning point `i` in clip space, and keep its
+    /// resources in group 1, bound with [`Self::points_bind_group_layout`].
+    pub fn with_points(
+        device: &Device,
+        format: TextureFormat,
+        blend: BlendState,
+        points_wgsl: &str,
+    ) -> Self {
+        let source = format!("{}\n{}", include_str!("shaders/polyline.wgsl"), points_wgsl);
+        let module = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("Polyline"),
+            source: ShaderSource::Wgsl(Cow::Owned(source)),
+        });
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            vertex: VertexState {
+                module: &module,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(FragmentState {
+                module: &module,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(ColorTargetState {
+                    format,
+                    blend: Some(blend),
+                    write_mask: Default::default(),
+                })],
+            }),
+            label: Some("Polyline"),
+            layout: None,
+            primitive: Default::default(),
+            depth_stencil: None,
+            multisample: Default::default(),
+            multiview_mask: None,
+            cache: None,
+        });
+
+        let params_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Polyline parameters"),
+            size: size_of::<Params>() as u64,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let params_bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: None,
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: params_buffer.as_entire_binding(),
+            }],
+        });
+
+        Self {
+            pipeline,
+            params_buffer,
+            params_bind_group,
+            point_count: 0,
+        }
+    }
+
+    /// Layout of group 1, where the points come from.
+    pub fn points_bind_group_layout(&self) -> BindGroupLayout {
+        self.pipeline.get_bind_group_layout(1)
+    }
+
+    /// Group 1 of [`Self::new`]. `points` needs `STORAGE` usage.
+    pub fn buffer_bind_group(&self, device: &Device, points: &Buffer) -> BindGroup {
+        device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Polyline points"),
+            layout: &self.points_bind_group_layout(),
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: points.as_entire_binding(),
+            }],
+        })
+    }
+
+    /// Sets what the next draws look like. `viewport` is the size of the render target in
+    /// pixels.
+    pub fn update(
+        &mut self,
+        queue: &Queue,
+        style: &LineStyle,
+        viewport: (u32, u32),
+        point_count: u32,
+    ) {
+        self.point_count = point_count;
+        let params = Params {
+            color: style.color,
+            viewport: [viewport.0 as f32, viewport.1 as f32],
+            half_width: style.width / 2.0,
+            join: style.join as u32,
+            point_count,
+            _pad: [0; 3],
+        };
+        queue.write_buffer(&self.params_buffer, 0, bytemuck::bytes_of(&params));
+    }
+
+    /// Draws the line through the points of the last [`Self::update`].
+    pub fn draw(&self, pass: &mut RenderPass, points: &BindGroup) {
+        self.bind(pass, points);
+        pass.draw(0..6, 0..self.point_count.saturating_sub(1));
+    }
+
+    /// Like [`Self::draw`], with the vertex and instance counts read from `args`, e.g. to draw
+    /// as many segments as a compute pass has generated. A line through `n` points takes 6
+    /// vertices and `n - 1` instances. The joints still use the point count of
+    /// [`Self::update`] to find the last point.
+    pub fn draw_indirect(&self, pass: &mut RenderPass, points: &BindGroup, args: &Buffer) {
+        self.bind(pass, points);
+        pass.draw_indirect(args, 0);
+    }
+
+    fn bind(&self, pass: &mut RenderPass, points: &BindGroup) {
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &self.params_bind_group, &[]);
+        pass.set_bind_group(1, points, &[]);
+    }
+}
@@ -0,0 +1,284 @@
+//! Per-pass GPU timing with timestamp queries.
+//!
+//! Each frame's passes get a begin/end timestamp pair in a shared query set. At the end of
+//! the frame the queries are resolved into one of a few readback buffers, which are mapped
+//! asynchronously and read back once ready, so profiling never stalls the render loop.
+//! Without [`Features::TIMESTAMP_QUERY`] every method is a no-op.
+
+use crate::readback::Readback;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use wgpu::wgt::PollType;
+use wgpu::{
+    Adapter, Buffer, BufferDescriptor, BufferUsages, CommandEncoder, ComputePassTimestampWrites,
+    Device, Features, QuerySet, QuerySetDescriptor, QueryType, Queue,
+    RenderPassTimestampWrites,
+};
+
+/// Maximum number of profiled passes per frame.
+const MAX_PASSES: u32 = 16;
+/// Frames whose results may be in flight at the same time.
+const READBACK_SLOTS: usize = 3;
+/// Samples the rolling statistics are computed over.
+const WINDOW: usize = 120;
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Copy, Clone, Debug, Serialize)]
+pub struct PassTiming {
+    pub label: &'static str,
+    /// Rolling mean in milliseconds
+    pub avg_ms: f32,
+    /// Rolling maximum in milliseconds
+    pub max_ms: f32,
+}
+
+struct ReadbackSlot {
+    buffer: Buffer,
+    labels: Vec<&'static str>,
+    /// [`Readback::Copied`] once the current frame's resolve was written into the buffer
+    state: Readback,
+    /// [`Inner::generation`] when the slot was written
+    generation: u32,
+}
+
+struct Inner {
+    device: Device,
+    query_set: QuerySet,
+    resolve_buffer: Buffer,
+    slots: Vec<ReadbackSlot>,
+    /// Slot the passes of the frame being recorded go to
+    current: Option<usize>,
+    /// Nanoseconds per timestamp tick
+    period: f32,
+    samples: Vec<(&'static str, VecDeque<f32>)>,
+    /// Bumped by [`GpuProfiler::reset`] so results still in flight are dropped
+    generation: u32,
+    last_report: Instant,
+}
+
+pub struct GpuProfiler {
+    inner: Option<Inner>,
+}
+
+impl GpuProfiler {
+    /// The profiling features `adapter` supports; request these when creating the device.
+    pub fn supported_features(adapter: &Adapter) -> Features {
+        adapter.features() & Features::TIMESTAMP_QUERY
+    }
+
+    pub fn new(device: &Device, queue: &Queue) -> Self {
+        if !device.features().contains(Features::TIMESTAMP_QUERY) {
+            return Self { inner: None };
+        }
+
+        let query_set = device.create_query_set(&QuerySetDescriptor {
+            label: Some("Profiler Query Set"),
+            ty: QueryType::Timestamp,
+            count: MAX_PASSES * 2,
+        });
+        let buffer_size = (MAX_PASSES * 2) as u64 * wgpu::QUERY_SIZE as u64;
+        let resolve_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Profiler Resolve Buffer"),
+            size: buffer_size,
+            usage: BufferUsages::QUERY_RESOLVE | BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let slots = (0..READBACK_SLOTS)
+            .map(|_| ReadbackSlot {
+                buffer: device.create_buffer(&BufferDescriptor {
+                    label: Some("Profiler Readback Buffer"),
+                    size: buffer_size,
+                    usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                }),
+                labels: Vec::new(),
+                state: Readback::Free,
+                generation: 0,
+            })
+            .collect();
+
+        Self {
+            inner: Some(Inner {
+                device: device.clone(),
+                query_set,
+                resolve_buffer,
+                slots,
+                current: None,
+                period: queue.get_timestamp_period(),
+                samples: Vec::new(),
+                generation: 0,
+                last_report: Instant::now(),
+            }),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.inner.is_some()
+    }
+
+    /// Timestamp writes for a render pass named `label`, or `None` if this pass isn't profiled.
+    pub fn render_pass(&mut self, label: &'static str) -> Option<RenderPassTimestampWrites<'_>> {
+        let inner = self.inner.as_mut()?;
+        let index = inner.allocate(label)?;
+        Some(RenderPassTimestampWrites {
+            query_set: &inner.query_set,
+            beginning_of_pass_write_index: Some(index * 2),
+            end_of_pass_write_index: Some(index * 2 + 1),
+        })
+    }
+
+    /// Timestamp writes for a compute pass named `label`, or `None` if this pass isn't profiled.
+    pub fn compute_pass(&mut self, label: &'static str) -> Option<ComputePassTimestampWrites<'_>> {
+        let inner = self.inner.as_mut()?;
+        let index = inner.allocate(label)?;
+        Some(ComputePassTimestampWrites {
+            query_set: &inner.query_set,
+            beginning_of_pass_write_index: Some(index * 2),
+            end_of_pass_write_index: Some(index * 2 + 1),
+        })
+    }
+
+    /// Resolves this frame's queries. Call on the frame's last encoder, before `finish()`.
+    pub fn resolve(&mut self, encoder: &mut CommandEncoder) {
+        let Some(inner) = &mut self.inner else {
+            return;
+        };
+        let Some(current) = inner.current.take() else {
+            return;
+        };
+        let slot = &mut inner.slots[current];
+        let count = slot.labels.len() as u32 * 2;
+        encoder.resolve_query_set(&inner.query_set, 0..count, &inner.resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(
+            &inner.resolve_buffer,
+            0,
+            &slot.buffer,
+            0,
+            Some(count as u64 * wgpu::QUERY_SIZE as u64),
+        );
+        slot.state.set_copied();
+    }
+
+    /// Starts reading back the resolved frame and collects finished ones.
+    /// Call after the frame has been submitted.
+    pub fn end_frame(&mut self) {
+        let Some(inner) = &mut self.inner else {
+            return;
+        };
+
+        for slot in &mut inner.slots {
+            slot.state.map(&slot.buffer);
+        }
+        let _ = inner.device.poll(PollType::Poll);
+
+        for i in 0..inner.slots.len() {
+            let slot = &mut inner.slots[i];
+            let size = slot.labels.len() * 2 * wgpu::QUERY_SIZE as usize;
+            let Some(timestamps) = slot.state.take(&slot.buffer, |bytes| {
+                bytemuck::cast_slice::<u8, u64>(&bytes[..size]).to_vec()
+            }) else {
+                continue;
+            };
+
+            let labels = std::mem::take(&mut slot.labels);
+            if slot.generation != inner.generation {
+                continue;
+            }
+            for (label, pair) in labels.into_iter().zip(timestamps.chunks_exact(2)) {
+                let ms = pair[1].wrapping_sub(pair[0]) as f32 * inner.period / 1_000_000.0;
+                inner.push_sample(label, ms);
+            }
+        }
+    }
+
+    /// Forgets all timings, e.g. when switching to a scene with different passes.
+    pub fn reset(&mut self) {
+        if let Some(inner) = &mut self.inner {
+            inner.samples.clear();
+            inner.generation = inner.generation.wrapping_add(1);
+        }
+    }
+
+    /// Rolling per-pass timings, in the order the passes were first seen.
+    pub fn timings(&self) -> Vec<PassTiming> {
+        let Some(inner) = &self.inner else {
+            return Vec::new();
+        };
+        inner
+            .samples
+            .iter()
+            .filter(|(_, s)| !s.is_empty())
+            .map(|(label, s)| PassTiming {
+                label,
+                avg_ms: s.iter().sum::<f32>() / s.len() as f32,
+                max_ms: s.iter().copied().fold(0.0, f32::max),
+            })
+            .collect()
+    }
+
+    /// One-line summary of [`Self::timings`].
+    pub fn summary(&self) -> String {
+        if !self.is_enabled() {
+            return "GPU timestamps unsupported".into();
+        }
+        let timings = self.timings();
+        let total: f32 = timings.iter().map(|x| x.avg_ms).sum();
+        let passes = timings
+            .iter()
+            .map(|x| format!("{}: {:.3} ms (max {:.3})", x.label, x.avg_ms, x.max_ms))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("GPU {:.3} ms [{}]", total, passes)
+    }
+
+    /// [`Self::summary`] at most once per second, for periodic printing.
+    pub fn report(&mut self) -> Option<String> {
+        let inner = self.inner.as_mut()?;
+        if inner.last_report.elapsed() < REPORT_INTERVAL {
+            return None;
+        }
+        inner.last_report = Instant::now();
+        Some(self.summary())
+    }
+}
+
+impl Inner {
+    /// Claims the next query pair of the current frame.
+    fn allocate(&mut self, label: &'static str) -> Option<u32> {
+        let current = match self.current {
+            Some(x) => x,
+            None => {
+                // all slots are still being read back; skip this frame
+                let free = self
+                    .slots
+                    .iter()
+                    .position(|x| x.state.is_free())?;
+                self.slots[free].labels.clear();
+                self.slots[free].generation = self.generation;
+                self.current = Some(free);
+                free
+            }
+        };
+        let labels = &mut self.slots[current].labels;
+        if labels.len() as u32 >= MAX_PASSES {
+            return None;
+        }
+        labels.push(label);
+        Some(labels.len() as u32 - 1)
+    }
+
+    fn push_sample(&mut self, label: &'static str, ms: f32) {
+        let samples = match self.samples.iter().position(|(l, _)| *l == label) {
+            Some(i) => &mut self.samples[i].1,
+            None => {
+                self.samples.push((label, VecDeque::with_capacity(WINDOW)));
+                &mut self.samples.last_mut().unwrap().1
+            }
+        };
+        if samples.len() == WINDOW {
+            samples.pop_front();
+        }
+        samples.push_back(ms);
+    }
+}
@@ -0,0 +1,78 @@
+//! Reading GPU results back without stalling the render loop.
+//!
+//! A frame records a copy into a `MAP_READ` buffer, the buffer is mapped asynchronously once
+//! the frame is submitted, and a later frame reads it when the mapping is done. A mapping that
+//! fails frees the buffer again instead of leaving it waiting forever.
+
+use log::warn;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+use wgpu::{Buffer, MapMode};
+
+const PENDING: u8 = 0;
+const MAPPED: u8 = 1;
+const FAILED: u8 = 2;
+
+#[derive(Default)]
+pub enum Readback {
+    #[default]
+    Free,
+    /// The copy was recorded in this frame, which hasn't been submitted yet
+    Copied,
+    /// `map_async` requested; the callback sets [`MAPPED`] or [`FAILED`]
+    Mapping(Arc<AtomicU8>),
+}
+
+impl Readback {
+    pub fn is_free(&self) -> bool {
+        matches!(self, Self::Free)
+    }
+
+    /// Call after recording the copy into the buffer.
+    pub fn set_copied(&mut self) {
+        *self = Self::Copied;
+    }
+
+    /// Starts mapping `buffer` if a copy into it was recorded. Call after the frame has been
+    /// submitted.
+    pub fn map(&mut self, buffer: &Buffer) {
+        if !matches!(self, Self::Copied) {
+            return;
+        }
+        let status = Arc::new(AtomicU8::new(PENDING));
+        let status_clone = Arc::clone(&status);
+        buffer.map_async(MapMode::Read, .., move |r| {
+            let value = match r {
+                Ok(()) => MAPPED,
+                Err(e) => {
+                    warn!("Failed to map readback buffer: {}", e);
+                    FAILED
+                }
+            };
+            status_clone.store(value, Ordering::Release);
+        });
+        *self = Self::Mapping(status);
+    }
+
+    /// Reads the mapped `buffer` with `read` and unmaps it, or returns `None` if the mapping
+    /// isn't done yet or failed. Either way a finished mapping frees the readback. The device
+    /// must have been polled for the mapping to finish.
+    pub fn take<T>(&mut self, buffer: &Buffer, read: impl FnOnce(&[u8]) -> T) -> Option<T> {
+        let Self::Mapping(status) = self else {
+            return None;
+        };
+        match status.load(Ordering::Acquire) {
+            MAPPED => {
+                let result = read(&buffer.get_mapped_range(..));
+                buffer.unmap();
+                *self = Self::Free;
+                Some(result)
+            }
+            FAILED => {
+                *self = Self::Free;
+                None
+            }
+            _ => None,
+        }
+    }
+}
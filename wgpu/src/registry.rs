@@ -0,0 +1,192 @@
+//! Every scene of the playground, by name.
+//!
+//! Shared by the desktop `playground` launcher and the Android app, so a new scene only has
+//! to be added to [`SCENES`] to show up in both.
+
+use crate::lissajous::LissajousScene;
+use crate::scene::{Frame, GpuContext, InputEvent, Key, Scene};
+use crate::triangle_rotation::TriangleScene;
+use crate::vsbm::VsbmScene;
+use crate::webgpu_bg::WebgpuBgScene;
+use serde::Serialize;
+use std::time::Duration;
+use wgpu::TextureFormat;
+
+/// Settings that apply to whichever scene is created.
+#[derive(Copy, Clone, Debug, Default, Serialize)]
+pub struct SceneOptions {
+    /// Frame rate scenes with an adaptive resolution try to hold
+    pub target_fps: Option<f32>,
+}
+
+impl SceneOptions {
+    /// `self`, with unset options taken from `defaults`.
+    pub fn or(self, defaults: SceneOptions) -> Self {
+        Self {
+            target_fps: self.target_fps.or(defaults.target_fps),
+        }
+    }
+}
+
+pub type SceneInit =
+    fn(&GpuContext, TextureFormat, (u32, u32), &SceneOptions) -> Box<dyn Scene>;
+
+/// Serializes to `{"name", "description", "defaults"}` for listing the scenes to other
+/// languages.
+#[derive(Serialize)]
+pub struct SceneEntry {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Options used for the ones the user leaves unset
+    pub defaults: SceneOptions,
+    #[serde(skip)]
+    pub init: SceneInit,
+}
+
+impl SceneEntry {
+    /// Creates the scene with `options`, falling back to [`Self::defaults`].
+    pub fn create(
+        &self,
+        gpu: &GpuContext,
+        format: TextureFormat,
+        size: (u32, u32),
+        options: &SceneOptions,
+    ) -> Box<dyn Scene> {
+        (self.init)(gpu, format, size, &options.or(self.defaults))
+    }
+}
+
+const NO_DEFAULTS: SceneOptions = SceneOptions { target_fps: None };
+
+/// Phone GPUs are far too weak for the ray marcher at native resolution; desktops render it
+/// at full resolution unless asked otherwise.
+const VSBM_DEFAULTS: SceneOptions = SceneOptions {
+    target_fps: if cfg!(target_os = "android") {
+        Some(30.0)
+    } else {
+        None
+    },
+};
+
+pub static SCENES: &[SceneEntry] = &[
+    SceneEntry {
+        name: "triangle-rotation",
+        description: "A vertex-colored triangle spinning in a uniform-driven vertex shader",
+        defaults: NO_DEFAULTS,
+        init: |gpu, format, size, _| Box::new(TriangleScene::new(gpu, format, size)),
+    },
+    SceneEntry {
+        name: "vsbm",
+        description: "Ray-marched fractal with an orbiting camera",
+        defaults: VSBM_DEFAULTS,
+        init: |gpu, format, size, options| {
+            let mut scene = VsbmScene::new(gpu, format, size);
+            scene.set_target_fps(options.target_fps);
+            Box::new(scene)
+        },
+    },
+    SceneEntry {
+        name: "lissajous",
+        description: "Lissajous curve; G switches CPU/vertex/compute generation, P the trail, O the settings",
+        defaults: NO_DEFAULTS,
+        init: |gpu, format, size, _| Box::new(LissajousScene::new(gpu, format, size)),
+    },
+    SceneEntry {
+        name: "webgpu-fundamental-bg",
+        description: "A thousand instanced, lit shapes tumbling in a spiral; G switches CPU/compute animation, C culling, M PBR, L lights, S shadows",
+        defaults: NO_DEFAULTS,
+        init: |gpu, format, size, _| Box::new(WebgpuBgScene::new(gpu, format, size)),
+    },
+];
+
+/// Index of the scene called `name` in [`SCENES`].
+pub fn find_scene(name: &str) -> Option<usize> {
+    SCENES.iter().position(|x| x.name == name)
+}
+
+/// Shows one scene of [`SCENES`] at a time and switches between them on the same device.
+///
+/// Keys 1-9 select a scene and Tab cycles through them; other input goes to the current scene.
+pub struct SceneSwitcher {
+    index: usize,
+    scene: Box<dyn Scene>,
+    options: SceneOptions,
+    format: TextureFormat,
+    size: (u32, u32),
+    /// Set when the scene changed, so the profiler can drop the old scene's passes
+    switched: bool,
+}
+
+impl SceneSwitcher {
+    pub fn new(
+        gpu: &GpuContext,
+        format: TextureFormat,
+        size: (u32, u32),
+        index: usize,
+        options: SceneOptions,
+    ) -> Self {
+        let scene = SCENES[index].create(gpu, format, size, &options);
+        Self {
+            index,
+            scene,
+            options,
+            format,
+            size,
+            switched: false,
+        }
+    }
+
+    pub fn current(&self) -> &'static SceneEntry {
+        &SCENES[self.index]
+    }
+
+    pub fn switch_to(&mut self, gpu: &GpuContext, index: usize) {
+        if index == self.index || index >= SCENES.len() {
+            return;
+        }
+        self.index = index;
+        self.scene = SCENES[index].create(gpu, self.format, self.size, &self.options);
+        self.switched = true;
+    }
+}
+
+impl Scene for SceneSwitcher {
+    fn update(&mut self, gpu: &GpuContext, dt: Duration) {
+        self.scene.update(gpu, dt);
+    }
+
+    fn render(&mut self, gpu: &GpuContext, frame: &mut Frame) {
+        if self.switched {
+            frame.profiler.reset();
+            self.switched = false;
+        }
+        self.scene.render(gpu, frame);
+    }
+
+    fn input(&mut self, gpu: &GpuContext, event: &InputEvent) -> bool {
+        match *event {
+            InputEvent::KeyPressed(Key::Tab) => {
+                self.switch_to(gpu, (self.index + 1) % SCENES.len());
+                true
+            }
+            InputEvent::KeyPressed(Key::Character(c @ '1'..='9')) => {
+                self.switch_to(gpu, c as usize - '1' as usize);
+                true
+            }
+            _ => self.scene.input(gpu, event),
+        }
+    }
+
+    fn resize(&mut self, gpu: &GpuContext, size: (u32, u32)) {
+        self.size = size;
+        self.scene.resize(gpu, size);
+    }
+
+    fn status(&self) -> Option<String> {
+        let name = self.current().name;
+        Some(match self.scene.status() {
+            Some(status) => format!("{}: {}", name, status),
+            None => name.into(),
+        })
+    }
+}
@@ -0,0 +1,110 @@
+//! Dynamic resolution: picks an internal render scale from measured frame times.
+
+use std::time::Instant;
+
+/// Frames to average before the scale is re-evaluated.
+const ADJUST_INTERVAL: u32 = 15;
+/// Smoothing factor of the frame time moving average.
+const SMOOTHING: f32 = 0.1;
+/// Relative frame time error that is tolerated without changing the scale.
+const TOLERANCE: f32 = 0.05;
+
+pub struct RenderScale {
+    scale: f32,
+    min_scale: f32,
+    max_scale: f32,
+    /// `None` keeps the scale fixed
+    target_frame_time: Option<f32>,
+    /// Exponential moving average of the frame time in seconds
+    frame_time: Option<f32>,
+    last_frame: Option<Instant>,
+    frames_since_adjust: u32,
+}
+
+impl RenderScale {
+    pub fn new() -> Self {
+        Self {
+            scale: 1.0,
+            min_scale: 0.25,
+            max_scale: 1.0,
+            target_frame_time: None,
+            frame_time: None,
+            last_frame: None,
+            frames_since_adjust: 0,
+        }
+    }
+
+    /// Sets the FPS to aim for, which must be positive and finite. `None` disables adaptation
+    /// and resets the scale to 1, as does an invalid value in release builds.
+    pub fn set_target_fps(&mut self, fps: Option<f32>) {
+        let valid = |x: &f32| *x > 0.0 && x.is_finite();
+        debug_assert!(fps.is_none_or(|x| valid(&x)), "Invalid target FPS: {:?}", fps);
+        let fps = fps.filter(valid);
+        self.target_frame_time = fps.map(|x| 1.0 / x);
+        if fps.is_none() {
+            self.scale = 1.0;
+        }
+    }
+
+    pub fn set_bounds(&mut self, min_scale: f32, max_scale: f32) {
+        self.min_scale = min_scale;
+        self.max_scale = max_scale;
+        self.scale = self.scale.clamp(min_scale, max_scale);
+    }
+
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Smoothed frames per second, or 0 before the second frame.
+    pub fn fps(&self) -> f32 {
+        self.frame_time.map(|x| 1.0 / x).unwrap_or(0.0)
+    }
+
+    /// Marks the start of a new frame. Returns `true` if the scale has changed.
+    pub fn frame(&mut self) -> bool {
+        let now = Instant::now();
+        if let Some(last) = self.last_frame.replace(now) {
+            let dt = (now - last).as_secs_f32();
+            self.frame_time = Some(match self.frame_time {
+                Some(avg) => avg + (dt - avg) * SMOOTHING,
+                None => dt,
+            });
+        }
+
+        let (Some(target), Some(frame_time)) = (self.target_frame_time, self.frame_time) else {
+            return false;
+        };
+        self.frames_since_adjust += 1;
+        if self.frames_since_adjust < ADJUST_INTERVAL {
+            return false;
+        }
+        self.frames_since_adjust = 0;
+
+        let ratio = target / frame_time;
+        if (ratio - 1.0).abs() < TOLERANCE {
+            return false;
+        }
+        // cost is proportional to the pixel count, i.e. to scale²;
+        // limit each step so a single slow frame doesn't make it jump
+        let new_scale =
+            (self.scale * ratio.sqrt().clamp(0.8, 1.25)).clamp(self.min_scale, self.max_scale);
+        let changed = new_scale != self.scale;
+        self.scale = new_scale;
+        changed
+    }
+
+    /// `size` multiplied by the current scale, at least 1x1.
+    pub fn apply(&self, size: (u32, u32)) -> (u32, u32) {
+        (
+            ((size.0 as f32 * self.scale).round() as u32).max(1),
+            ((size.1 as f32 * self.scale).round() as u32).max(1),
+        )
+    }
+}
+
+impl Default for RenderScale {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,394 @@
+//! Platform-independent scene plumbing.
+//!
+//! A [`Scene`] only knows how to draw itself into a texture view; a [`SceneHost`] owns the
+//! surface around it and takes care of frame acquisition, surface reconfiguration, animation
+//! time, pausing and GPU profiling. The winit runner in [`crate::app`] and the Android JNI
+//! glue both drive scenes through a [`SceneHost`].
+
+use crate::profiler::GpuProfiler;
+use crate::time_source::TimeSource;
+use crate::WgpuStateInitInfo;
+use log::warn;
+use std::time::Duration;
+use wgpu::{
+    Adapter, CommandEncoder, Device, Instance, PresentMode, Queue, Surface, SurfaceConfiguration,
+    SurfaceError, TextureFormat, TextureView,
+};
+
+/// The device a scene renders with.
+pub struct GpuContext {
+    pub instance: Instance,
+    pub adapter: Adapter,
+    pub device: Device,
+    pub queue: Queue,
+}
+
+impl GpuContext {
+    /// Picks a high-performance adapter that can present to `compatible_surface`, and
+    /// requests a device with the profiling features it supports.
+    pub async fn new(
+        instance: Instance,
+        compatible_surface: Option<&Surface<'_>>,
+    ) -> anyhow::Result<Self> {
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::HighPerformance,
+                compatible_surface,
+                force_fallback_adapter: false,
+            })
+            .await?;
+        let (device, queue) = adapter
+            .request_device(&wgpu::DeviceDescriptor {
+                required_features: GpuProfiler::supported_features(&adapter),
+                ..Default::default()
+            })
+            .await?;
+        Ok(Self {
+            instance,
+            adapter,
+            device,
+            queue,
+        })
+    }
+}
+
+/// What a scene records one frame into.
+pub struct Frame<'a> {
+    pub encoder: &'a mut CommandEncoder,
+    /// sRGB view of the surface texture
+    pub view: &'a TextureView,
+    pub size: (u32, u32),
+    pub profiler: &'a mut GpuProfiler,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum InputEvent {
+    KeyPressed(Key),
+    /// Mouse button or touch down, in physical pixels from the top-left corner. `id` tells
+    /// simultaneous touches apart; the mouse is always 0.
+    PointerPressed {
+        id: u32,
+        x: f32,
+        y: f32,
+    },
+    PointerMoved {
+        id: u32,
+        x: f32,
+        y: f32,
+    },
+    PointerReleased {
+        id: u32,
+        x: f32,
+        y: f32,
+    },
+    /// Two-finger pinch or mouse wheel. `scale` is the factor the distance between the fingers
+    /// changed by since the last event; above 1 when spreading them.
+    Pinch {
+        scale: f32,
+    },
+    DoubleTap {
+        x: f32,
+        y: f32,
+    },
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Key {
+    Space,
+    Enter,
+    Escape,
+    Tab,
+    ArrowLeft,
+    ArrowRight,
+    ArrowUp,
+    ArrowDown,
+    Character(char),
+}
+
+/// A demo that can be shown by a [`SceneHost`].
+///
+/// Scenes are created by an init function taking the [`GpuContext`], the format of the views
+/// they'll render to and the initial size; see [`SceneHost::new`].
+pub trait Scene {
+    /// Advances the animation by `dt`, which is zero while paused.
+    fn update(&mut self, gpu: &GpuContext, dt: Duration);
+
+    /// Records the frame. The scene must clear `frame.view` itself.
+    fn render(&mut self, gpu: &GpuContext, frame: &mut Frame);
+
+    /// Returns `true` if the event was consumed.
+    fn input(&mut self, _gpu: &GpuContext, _event: &InputEvent) -> bool {
+        false
+    }
+
+    /// Called after the surface has been resized to `size`.
+    fn resize(&mut self, _gpu: &GpuContext, _size: (u32, u32)) {}
+
+    /// Scene-specific text appended to the periodic frame statistics.
+    fn status(&self) -> Option<String> {
+        None
+    }
+}
+
+impl<S: Scene + ?Sized> Scene for Box<S> {
+    fn update(&mut self, gpu: &GpuContext, dt: Duration) {
+        (**self).update(gpu, dt)
+    }
+
+    fn render(&mut self, gpu: &GpuContext, frame: &mut Frame) {
+        (**self).render(gpu, frame)
+    }
+
+    fn input(&mut self, gpu: &GpuContext, event: &InputEvent) -> bool {
+        (**self).input(gpu, event)
+    }
+
+    fn resize(&mut self, gpu: &GpuContext, size: (u32, u32)) {
+        (**self).resize(gpu, size)
+    }
+
+    fn status(&self) -> Option<String> {
+        (**self).status()
+    }
+}
+
+/// Owns a surface and the scene presented on it.
+///
+/// The surface can be detached and replaced (e.g. when an Android window is destroyed and
+/// recreated) while the device and the scene live on.
+pub struct SceneHost<S> {
+    gpu: GpuContext,
+    /// `None` while detached
+    surface: Option<Surface<'static>>,
+    surface_config: SurfaceConfiguration,
+    view_format: TextureFormat,
+    profiler: GpuProfiler,
+    time_source: TimeSource,
+    last_time: Option<f32>,
+    paused: bool,
+    scene: S,
+}
+
+impl<S: Scene> SceneHost<S> {
+    pub async fn new(
+        info: WgpuStateInitInfo,
+        init: impl FnOnce(&GpuContext, TextureFormat, (u32, u32)) -> S,
+    ) -> anyhow::Result<Self> {
+        let gpu = GpuContext::new(info.instance, Some(&info.surface)).await?;
+
+        let surface_caps = info.surface.get_capabilities(&gpu.adapter);
+        let format = surface_caps.formats[0];
+        // Without add_srgb_suffix() the image we will be working with
+        // might not be "gamma correct".
+        let view_format = format.add_srgb_suffix();
+        let surface_config = SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format,
+            view_formats: vec![view_format],
+            alpha_mode: wgpu::CompositeAlphaMode::Auto,
+            width: info.size.0.max(1),
+            height: info.size.1.max(1),
+            desired_maximum_frame_latency: 2,
+            present_mode: PresentMode::AutoVsync,
+        };
+        info.surface.configure(&gpu.device, &surface_config);
+
+        let profiler = GpuProfiler::new(&gpu.device, &gpu.queue);
+        let scene = init(&gpu, view_format, info.size);
+        Ok(Self {
+            gpu,
+            surface: Some(info.surface),
+            surface_config,
+            view_format,
+            profiler,
+            time_source: TimeSource::real(),
+            last_time: None,
+            paused: false,
+            scene,
+        })
+    }
+
+    /// Replaces the scene with `init`'s, keeping the device and the surface. Timings of the old
+    /// scene are dropped from the profiler.
+    pub fn replace_scene(
+        &mut self,
+        init: impl FnOnce(&GpuContext, TextureFormat, (u32, u32)) -> S,
+    ) {
+        self.scene = init(&self.gpu, self.view_format, self.size());
+        self.profiler.reset();
+        self.last_time = None;
+    }
+
+    /// Drops the surface. Frames are skipped until [`Self::attach_surface`].
+    pub fn detach_surface(&mut self) {
+        self.surface = None;
+    }
+
+    /// Presents to `surface`, created from [`GpuContext::instance`], from now on. It must
+    /// support the format the scene renders in. The animation resumes without a jump.
+    pub fn attach_surface(
+        &mut self,
+        surface: Surface<'static>,
+        size: (u32, u32),
+    ) -> anyhow::Result<()> {
+        let caps = surface.get_capabilities(&self.gpu.adapter);
+        if !caps.formats.contains(&self.surface_config.format) {
+            anyhow::bail!(
+                "New surface doesn't support {:?}; supported: {:?}",
+                self.surface_config.format,
+                caps.formats
+            );
+        }
+        self.surface = Some(surface);
+        self.last_time = None;
+        if size.0 != 0 && size.1 != 0 && size != self.size() {
+            self.surface_config.width = size.0;
+            self.surface_config.height = size.1;
+            self.scene.resize(&self.gpu, size);
+        }
+        self.configure_surface();
+        Ok(())
+    }
+
+    pub fn has_surface(&self) -> bool {
+        self.surface.is_some()
+    }
+
+    pub fn gpu(&self) -> &GpuContext {
+        &self.gpu
+    }
+
+    pub fn scene(&self) -> &S {
+        &self.scene
+    }
+
+    pub fn scene_mut(&mut self) -> &mut S {
+        &mut self.scene
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.surface_config.width, self.surface_config.height)
+    }
+
+    /// Format of the views passed to [`Scene::render`].
+    pub fn view_format(&self) -> TextureFormat {
+        self.view_format
+    }
+
+    pub fn set_present_mode(&mut self, present_mode: PresentMode) {
+        self.surface_config.present_mode = present_mode;
+        self.configure_surface();
+    }
+
+    /// Replaces the clock `dt` is derived from. Defaults to [`TimeSource::real`].
+    pub fn set_time_source(&mut self, time_source: TimeSource) {
+        self.time_source = time_source;
+        self.last_time = None;
+    }
+
+    /// Makes the next frame advance the animation by zero, e.g. after rendering was suspended.
+    pub fn reset_clock(&mut self) {
+        self.last_time = None;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Freezes the animation; frames are still rendered.
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    /// Per-pass GPU timings of the frames rendered so far.
+    pub fn profiler(&mut self) -> &mut GpuProfiler {
+        &mut self.profiler
+    }
+
+    pub fn resize(&mut self, new_size: (u32, u32)) {
+        // a zero-sized surface can't be configured (e.g. minimized windows)
+        if new_size.0 == 0 || new_size.1 == 0 || new_size == self.size() {
+            return;
+        }
+        self.surface_config.width = new_size.0;
+        self.surface_config.height = new_size.1;
+        self.configure_surface();
+        self.scene.resize(&self.gpu, new_size);
+    }
+
+    /// Forwards `event` to the scene. Space and double taps toggle pausing unless the scene
+    /// consumed them.
+    pub fn input(&mut self, event: &InputEvent) -> bool {
+        if self.scene.input(&self.gpu, event) {
+            return true;
+        }
+        match event {
+            InputEvent::KeyPressed(Key::Space) | InputEvent::DoubleTap { .. } => {
+                self.paused = !self.paused;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Updates and renders one frame, returning whether it was presented. A lost or outdated
+    /// surface is reconfigured and the frame skipped; only unrecoverable errors are returned.
+    /// Does nothing while detached.
+    pub fn frame(&mut self, before_present: impl FnOnce()) -> Result<bool, SurfaceError> {
+        let Some(surface) = &self.surface else {
+            return Ok(false);
+        };
+        let time = self.time_source.next_frame();
+        let dt = match self.last_time.replace(time) {
+            Some(last) if !self.paused => Duration::from_secs_f32((time - last).max(0.0)),
+            _ => Duration::ZERO,
+        };
+        self.scene.update(&self.gpu, dt);
+
+        let surface_texture = match surface.get_current_texture() {
+            Ok(x) => x,
+            Err(SurfaceError::Lost | SurfaceError::Outdated) => {
+                self.configure_surface();
+                return Ok(false);
+            }
+            Err(SurfaceError::Timeout) => {
+                warn!("Timed out acquiring the surface texture; skipping frame");
+                return Ok(false);
+            }
+            Err(e) => return Err(e),
+        };
+        let view = surface_texture
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor {
+                format: Some(self.view_format),
+                ..Default::default()
+            });
+
+        let mut encoder = self
+            .gpu
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Render Encoder"),
+            });
+        let mut frame = Frame {
+            encoder: &mut encoder,
+            view: &view,
+            size: self.size(),
+            profiler: &mut self.profiler,
+        };
+        self.scene.render(&self.gpu, &mut frame);
+        self.profiler.resolve(&mut encoder);
+
+        self.gpu.queue.submit([encoder.finish()]);
+        before_present();
+        surface_texture.present();
+        self.profiler.end_frame();
+        Ok(true)
+    }
+
+    fn configure_surface(&self) {
+        if let Some(surface) = &self.surface {
+            surface.configure(&self.gpu.device, &self.surface_config);
+        }
+    }
+}
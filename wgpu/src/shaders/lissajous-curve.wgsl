@@ -0,0 +1,20 @@
+// Parametric curve
+//   p(t) = amplitude * exp(-damping * t) * (sin(fx * t + px), sin(fy * t + py)),
+// with t from 0 to 2π * turns over `segments` points. Must match `Curve::point` in lissajous.rs.
+
+struct Curve {
+    frequency: vec2f,
+    phase: vec2f,
+    damping: f32,
+    amplitude: f32,
+    turns: f32,
+    segments: u32,
+}
+
+const TAU: f32 = 6.283185307179586;
+
+fn curve_point(c: Curve, i: u32) -> vec2f {
+    let t = f32(i) / f32(max(c.segments, 2u) - 1u) * TAU * c.turns;
+    let envelope = c.amplitude * exp(-c.damping * t);
+    return envelope * sin(c.frequency * t + c.phase);
+}
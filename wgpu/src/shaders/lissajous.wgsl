@@ -1,19 +1,25 @@
-struct VertexInput {
-    @location(0) pos: vec2<f32>,
-};
+// Compute generation of the curve: fills the point buffer and the draw arguments of a
+// polyline through it. Appended to lissajous-curve.wgsl.
 
-struct VertexOutput {
-    @builtin(position) clip_position: vec4<f32>,
-};
-
-@vertex
-fn vs_main(model: VertexInput) -> VertexOutput {
-    var out: VertexOutput;
-    out.clip_position = vec4<f32>(model.pos, 0.0, 1.0);
-    return out;
+struct DrawIndirectArgs {
+    vertex_count: u32,
+    instance_count: u32,
+    first_vertex: u32,
+    first_instance: u32,
 }
 
-@fragment
-fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return vec4<f32>(0.0, 1.0, 0.8, 1.0); // 赛博朋克青色
-}
\ No newline at end of file
+@group(0) @binding(0) var<uniform> curve: Curve;
+@group(0) @binding(1) var<storage, read_write> points: array<vec2f>;
+@group(0) @binding(2) var<storage, read_write> draw_args: DrawIndirectArgs;
+
+@compute @workgroup_size(64)
+fn cs_generate(@builtin(global_invocation_id) id: vec3u) {
+    let i = id.x;
+    if i == 0u {
+        // a quad per segment, see polyline.wgsl
+        draw_args = DrawIndirectArgs(6u, curve.segments - 1u, 0u, 0u);
+    }
+    if i < curve.segments {
+        points[i] = curve_point(curve, i);
+    }
+}
@@ -0,0 +1,51 @@
+// Text and boxes of overlay.rs: a quad per instance, positioned in character cells and
+// covered with a glyph of the font texture.
+
+struct Params {
+    viewport: vec2f,
+    // size of a character cell in pixels
+    cell: vec2f,
+}
+
+struct Instance {
+    // top-left corner, in cells
+    @location(0) position: vec2f,
+    // in cells; the glyph is stretched over it
+    @location(1) size: vec2f,
+    @location(2) color: vec4f,
+    @location(3) glyph: u32,
+}
+
+struct VertexOutput {
+    @builtin(position) pos: vec4f,
+    // in texels of the font texture
+    @location(0) texel: vec2f,
+    @location(1) color: vec4f,
+}
+
+// Must match `GLYPH_CELL` in overlay.rs.
+const GLYPH_CELL: vec2f = vec2f(6.0, 8.0);
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var font: texture_2d<f32>;
+
+// a triangle strip of 4 vertices
+@vertex
+fn vs_main(@builtin(vertex_index) idx: u32, instance: Instance) -> VertexOutput {
+    let corner = vec2f(f32(idx & 1u), f32(idx >> 1u));
+    let pixel = (instance.position + corner * instance.size) * params.cell;
+    var out: VertexOutput;
+    out.pos = vec4f(pixel / params.viewport * vec2f(2.0, -2.0) + vec2f(-1.0, 1.0), 0.0, 1.0);
+    out.texel = (vec2f(f32(instance.glyph), 0.0) + corner) * GLYPH_CELL;
+    out.color = instance.color;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4f {
+    let coverage = textureLoad(font, vec2i(floor(in.texel)), 0).r;
+    if coverage == 0.0 {
+        discard;
+    }
+    return in.color;
+}
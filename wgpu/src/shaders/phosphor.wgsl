@@ -0,0 +1,60 @@
+// Passes of phosphor.rs: fading the trace, blurring it for the glow and showing both.
+
+struct Blur {
+    // distance between taps, in texture coordinates
+    step: vec2f,
+}
+
+struct Composite {
+    glow_strength: f32,
+}
+
+struct VertexOutput {
+    @builtin(position) pos: vec4f,
+    @location(0) uv: vec2f,
+}
+
+@group(0) @binding(0) var src_texture: texture_2d<f32>;
+@group(0) @binding(1) var src_sampler: sampler;
+@group(0) @binding(2) var<uniform> blur: Blur;
+
+@group(0) @binding(3) var trace_texture: texture_2d<f32>;
+@group(0) @binding(4) var glow_texture: texture_2d<f32>;
+@group(0) @binding(5) var<uniform> composite: Composite;
+
+// a single triangle covering the whole viewport
+@vertex
+fn vs_main(@builtin(vertex_index) idx: u32) -> VertexOutput {
+    let uv = vec2f(f32((idx << 1u) & 2u), f32(idx & 2u));
+    var out: VertexOutput;
+    out.pos = vec4f(uv * vec2f(2.0, -2.0) + vec2f(-1.0, 1.0), 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+// Multiplied onto the trace by the blend state; the fade is the blend constant.
+@fragment
+fn fs_decay() -> @location(0) vec4f {
+    return vec4f(0.0);
+}
+
+// One direction of a gaussian blur, taps half a standard deviation apart
+@fragment
+fn fs_blur(in: VertexOutput) -> @location(0) vec4f {
+    var sum = vec4f(0.0);
+    var total = 0.0;
+    for (var i = -4; i <= 4; i++) {
+        let x = f32(i) * 0.5;
+        let weight = exp(-0.5 * x * x);
+        sum += textureSample(src_texture, src_sampler, in.uv + blur.step * f32(i)) * weight;
+        total += weight;
+    }
+    return sum / total;
+}
+
+@fragment
+fn fs_composite(in: VertexOutput) -> @location(0) vec4f {
+    let trace = textureSample(trace_texture, src_sampler, in.uv).rgb;
+    let glow = textureSample(glow_texture, src_sampler, in.uv).rgb;
+    return vec4f(trace + glow * composite.glow_strength, 1.0);
+}
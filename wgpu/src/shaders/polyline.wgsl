@@ -0,0 +1,134 @@
+// Thick anti-aliased polyline. Segment `i` (the instance) joins points `i` and `i + 1` with a
+// quad of 6 vertices expanded in pixel space.
+//
+// The points come from `fn polyline_point(i: u32) -> vec2f` (clip space), which polyline.rs
+// appends to this file; its resources live in group 1.
+
+struct Params {
+    color: vec4f,
+    viewport: vec2f,
+    half_width: f32,
+    join: u32,
+    point_count: u32,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4f,
+    // signed distance from the segment's center line, in pixels
+    @location(0) offset: f32,
+    // segment ends, in pixels
+    @location(1) @interpolate(flat) start: vec2f,
+    @location(2) @interpolate(flat) end: vec2f,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+
+// Must match `Join` in polyline.rs.
+const JOIN_MITER: u32 = 0u;
+const JOIN_ROUND: u32 = 1u;
+// Longest miter, in half widths
+const MITER_LIMIT: f32 = 4.0;
+// Pixels the quads extend beyond the stroke for the anti-aliased edge
+const FEATHER: f32 = 1.0;
+
+fn to_pixels(p: vec2f) -> vec2f {
+    return (vec2f(p.x, -p.y) * 0.5 + 0.5) * params.viewport;
+}
+
+fn to_clip(p: vec2f) -> vec4f {
+    let ndc = p / params.viewport * 2.0 - 1.0;
+    return vec4f(ndc.x, -ndc.y, 0.0, 1.0);
+}
+
+fn point_pixels(i: u32) -> vec2f {
+    return to_pixels(polyline_point(i));
+}
+
+fn direction(a: vec2f, b: vec2f, fallback: vec2f) -> vec2f {
+    let d = b - a;
+    let len = length(d);
+    if len < 1e-4 {
+        return fallback;
+    }
+    return d / len;
+}
+
+fn normal(dir: vec2f) -> vec2f {
+    return vec2f(-dir.y, dir.x);
+}
+
+// Offset from a joint to the corner on the normal side of a segment with direction `dir`, whose
+// neighbor there has direction `other`.
+fn miter(dir: vec2f, other: vec2f, radius: f32) -> vec2f {
+    let n = normal(dir);
+    let sum = n + normal(other);
+    // turning back on itself
+    if length(sum) < 1e-3 {
+        return n * radius;
+    }
+    let m = normalize(sum);
+    return m * min(radius / max(dot(m, n), 1e-3), MITER_LIMIT * radius);
+}
+
+@vertex
+fn vs_main(
+    @builtin(vertex_index) vi: u32,
+    @builtin(instance_index) segment: u32,
+) -> VertexOutput {
+    // two triangles; x: start or end of the segment, y: right or left side
+    var corners = array(
+        vec2u(0u, 0u), vec2u(1u, 0u), vec2u(0u, 1u),
+        vec2u(0u, 1u), vec2u(1u, 0u), vec2u(1u, 1u),
+    );
+    let corner = corners[vi];
+    let start = point_pixels(segment);
+    let end = point_pixels(segment + 1u);
+    let dir = direction(start, end, vec2f(1.0, 0.0));
+    let n = normal(dir);
+    let side = f32(corner.y) * 2.0 - 1.0;
+    let radius = params.half_width + FEATHER;
+
+    var joint = start;
+    var offset: vec2f;
+    if params.join == JOIN_ROUND {
+        // a rectangle around the capsule the fragment shader cuts out
+        var along = -dir;
+        if corner.x == 1u {
+            joint = end;
+            along = dir;
+        }
+        offset = (along + n * side) * radius;
+    } else {
+        // butt ends, and miters shared with the neighbors in between
+        var other = dir;
+        if corner.x == 0u && segment > 0u {
+            other = direction(point_pixels(segment - 1u), start, dir);
+        }
+        if corner.x == 1u {
+            joint = end;
+            if segment + 2u < params.point_count {
+                other = direction(end, point_pixels(segment + 2u), dir);
+            }
+        }
+        offset = miter(dir, other, radius) * side;
+    }
+
+    return VertexOutput(to_clip(joint + offset), dot(offset, n), start, end);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4f {
+    var distance = abs(in.offset);
+    if params.join == JOIN_ROUND {
+        let p = in.position.xy - in.start;
+        let segment = in.end - in.start;
+        let t = clamp(dot(p, segment) / max(dot(segment, segment), 1e-6), 0.0, 1.0);
+        distance = length(p - segment * t);
+    }
+    // fraction of the pixel covered by the stroke
+    let coverage = clamp(params.half_width + 0.5 - distance, 0.0, 1.0);
+    if coverage <= 0.0 {
+        discard;
+    }
+    return vec4f(params.color.rgb, params.color.a * coverage);
+}
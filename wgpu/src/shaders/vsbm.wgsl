@@ -10,6 +10,9 @@ struct Uniforms {
     screen_size: vec2f,
     len: f32,
     _p5: f32,
+    // maps this pass's uv onto the full image (tiled export)
+    tile_scale: vec2f,
+    tile_offset: vec2f,
 };
 
 @group(0) @binding(0) var<uniform> ui: Uniforms;
@@ -46,7 +49,8 @@ fn kernel(ver: vec3f) -> f32 {
 }
 
 @fragment
-fn fs_main(@location(0) uv: vec2f) -> @location(0) vec4f {
+fn fs_main(@location(0) pass_uv: vec2f) -> @location(0) vec4f {
+    let uv = pass_uv * ui.tile_scale + ui.tile_offset;
     let M_L = 0.381966;
     let M_R = 0.618033;
     let step_size = 0.002;
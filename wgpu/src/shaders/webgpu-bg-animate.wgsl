@@ -0,0 +1,45 @@
+// Instance transforms of webgpu_bg.rs: translate(offset) * rotate_z(t) * rotate_x(0.9 t) * 3,
+// the same as `InstanceInfo::matrix` there.
+
+struct Params {
+    time: f32,
+    count: u32,
+}
+
+struct InstanceInfo {
+    offset: vec3f,
+    // fraction of a turn the instance is ahead of the first one
+    time_offset: f32,
+}
+
+struct Inst {
+    mat: mat4x4f,
+}
+
+const TAU: f32 = 6.283185307179586;
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> infos: array<InstanceInfo>;
+@group(0) @binding(2) var<storage, read_write> instances: array<Inst>;
+
+// Must match `WORKGROUP_SIZE` in webgpu_bg.rs.
+@compute @workgroup_size(64)
+fn cs_animate(@builtin(global_invocation_id) id: vec3u) {
+    let i = id.x;
+    if i >= params.count {
+        return;
+    }
+    let info = infos[i];
+    let t = params.time * 0.1 + info.time_offset * TAU;
+    let z = vec2f(cos(t), sin(t));
+    let x = vec2f(cos(t * 0.9), sin(t * 0.9));
+    let rotate_z = mat3x3f(vec3f(z.x, z.y, 0.0), vec3f(-z.y, z.x, 0.0), vec3f(0.0, 0.0, 1.0));
+    let rotate_x = mat3x3f(vec3f(1.0, 0.0, 0.0), vec3f(0.0, x.x, x.y), vec3f(0.0, -x.y, x.x));
+    let m = rotate_z * rotate_x * 3.0;
+    instances[i].mat = mat4x4f(
+        vec4f(m[0], 0.0),
+        vec4f(m[1], 0.0),
+        vec4f(m[2], 0.0),
+        vec4f(info.offset, 1.0),
+    );
+}
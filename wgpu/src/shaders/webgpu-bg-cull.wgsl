@@ -0,0 +1,51 @@
+// Frustum culling of webgpu_bg.rs: the instances whose bounding spheres touch the view frustum
+// are appended to `visible`, which the indirect draw goes through, and counted in its arguments.
+
+struct Params {
+    // facing inward and normalized: p is inside a plane when dot(plane.xyz, p) + plane.w >= 0
+    planes: array<vec4f, 6>,
+    // bounding sphere of the mesh around its origin
+    radius: f32,
+    count: u32,
+    // 0 lets every instance through
+    enabled: u32,
+}
+
+struct Inst {
+    mat: mat4x4f,
+}
+
+// The arguments of `draw_indirect`; `vertex_count` is set, `instance_count` zeroed before.
+struct DrawIndirectArgs {
+    vertex_count: u32,
+    instance_count: atomic<u32>,
+    first_vertex: u32,
+    first_instance: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> instances: array<Inst>;
+@group(0) @binding(2) var<storage, read_write> visible: array<u32>;
+@group(0) @binding(3) var<storage, read_write> draw_args: DrawIndirectArgs;
+
+// Must match `WORKGROUP_SIZE` in webgpu_bg.rs.
+@compute @workgroup_size(64)
+fn cs_cull(@builtin(global_invocation_id) id: vec3u) {
+    let i = id.x;
+    if i >= params.count {
+        return;
+    }
+    let m = instances[i].mat;
+    let center = m[3].xyz;
+    let scale = max(length(m[0].xyz), max(length(m[1].xyz), length(m[2].xyz)));
+    let radius = params.radius * scale;
+    if params.enabled != 0u {
+        for (var p = 0; p < 6; p++) {
+            let plane = params.planes[p];
+            if dot(plane.xyz, center) + plane.w < -radius {
+                return;
+            }
+        }
+    }
+    visible[atomicAdd(&draw_args.instance_count, 1u)] = i;
+}
@@ -1,110 +1,208 @@
- struct Uniforms {
+// Instanced meshes of webgpu_bg.rs, lit by a list of directional, point and spot lights with
+// either the original wrapped Blinn-Phong or metallic-roughness PBR. The base color comes from
+// the vertices. One directional light can cast shadows, from a shadow map rendered by
+// `shadowVSMain` and filtered with PCF.
 
+struct Uniforms {
     viewProjection: mat4x4f,
-
     viewPosition: vec3f,
-
-    lightPosition: vec3f,
-
+    lightCount: u32,
+    // SHADING_*
+    shading: u32,
     shininess: f32,
-
-  };
-
-
-  @group(0) @binding(0) var<uniform> uni: Uniforms;
-
-
-  struct Inst {
-
+    metallic: f32,
+    roughness: f32,
+    // maps world space to the shadow map: xy to clip space, z to depth
+    lightViewProjection: mat4x4f,
+    // index of the light casting shadows; lightCount or more for none
+    shadowLight: u32,
+    // in shadow map depth
+    shadowBias: f32,
+    // in world units, along the surface normal
+    normalBias: f32,
+    // texels around the center the PCF kernel reaches out
+    pcfRadius: i32,
+};
+
+struct Inst {
     mat: mat4x4f,
+};
 
-  };
+// Must match `GpuLight` in webgpu_bg.rs.
+struct Light {
+    position: vec3f,
+    // LIGHT_*
+    kind: u32,
+    // where the light shines to
+    direction: vec3f,
+    intensity: f32,
+    color: vec3f,
+    // distance at which point and spot lights fade out; 0 for no falloff
+    range: f32,
+    // cosines of the inner and outer half angles of spot lights
+    cone: vec2f,
+};
 
+const LIGHT_DIRECTIONAL: u32 = 0u;
+const LIGHT_POINT: u32 = 1u;
+const LIGHT_SPOT: u32 = 2u;
 
-  @group(0) @binding(1) var<storage, read> perInst: array<Inst>;
+const SHADING_BLINN_PHONG: u32 = 0u;
+const SHADING_PBR: u32 = 1u;
 
+const PI: f32 = 3.141592653589793;
 
-  struct VSInput {
+@group(0) @binding(0) var<uniform> uni: Uniforms;
 
-      @location(0) position: vec4f,
+@group(0) @binding(1) var<storage, read> perInst: array<Inst>;
 
-      @location(1) normal: vec3f,
+// indices into perInst of the instances that survived culling
+@group(0) @binding(2) var<storage, read> visible: array<u32>;
 
-      @location(2) color: vec4f,
+@group(0) @binding(3) var<storage, read> lights: array<Light>;
 
-  };
+@group(1) @binding(0) var shadowMap: texture_depth_2d;
 
+@group(1) @binding(1) var shadowSampler: sampler_comparison;
 
-  struct VSOutput {
+struct VSInput {
+    @location(0) position: vec4f,
+    @location(1) normal: vec3f,
+    @location(2) color: vec4f,
+};
 
+struct VSOutput {
     @builtin(position) position: vec4f,
-
     @location(0) normal: vec3f,
-
     @location(1) color: vec4f,
+    @location(2) worldPosition: vec3f,
+};
 
-    @location(2) surfaceToLight: vec3f,
-
-    @location(3) surfaceToView: vec3f,
-
-  };
-
-
-  @vertex
-
-  fn myVSMain(v: VSInput, @builtin(instance_index) instanceIndex: u32) -> VSOutput {
-
+@vertex
+fn myVSMain(v: VSInput, @builtin(instance_index) instanceIndex: u32) -> VSOutput {
     var vsOut: VSOutput;
-
-    let world = perInst[instanceIndex].mat;
-
+    let world = perInst[visible[instanceIndex]].mat;
     vsOut.position = uni.viewProjection * world * v.position;
-
     vsOut.normal = (world * vec4f(v.normal, 0)).xyz;
-
     vsOut.color = v.color;
-
-
-    let surfaceWorldPosition = (world * v.position).xyz;
-
-    vsOut.surfaceToLight = uni.lightPosition - surfaceWorldPosition;
-
-    vsOut.surfaceToView = uni.viewPosition - surfaceWorldPosition;
-
-
+    vsOut.worldPosition = (world * v.position).xyz;
     return vsOut;
-
-  }
-
-
-  @fragment
-
-  fn myFSMain(v: VSOutput) -> @location(0) vec4f {
-
-    var normal = normalize(v.normal);
-
-
-    let surfaceToLightDirection = normalize(v.surfaceToLight);
-
-    let surfaceToViewDirection = normalize(v.surfaceToView);
-
-    let halfVector = normalize(surfaceToLightDirection + surfaceToViewDirection);
-
-
-    let light = dot(normal, surfaceToLightDirection) * 0.5 + 0.5;
-
- 
-
-    var specular = 0.0;
-
-    if (light > 0.0) {
-
-      specular = pow(dot(normal, halfVector), uni.shininess);
-
+}
+
+// Depth of every instance, not only the visible ones, seen from the light casting shadows.
+@vertex
+fn shadowVSMain(@location(0) position: vec4f, @builtin(instance_index) instanceIndex: u32) -> @builtin(position) vec4f {
+    return uni.lightViewProjection * perInst[instanceIndex].mat * position;
+}
+
+// How much of the light casting shadows reaches the surface, from 0 in shadow to 1.
+fn shadow(position: vec3f, normal: vec3f) -> f32 {
+    let clip = uni.lightViewProjection * vec4f(position + normal * uni.normalBias, 1.0);
+    let ndc = clip.xyz / clip.w;
+    let uv = ndc.xy * vec2f(0.5, -0.5) + 0.5;
+    if any(uv < vec2f(0.0)) || any(uv > vec2f(1.0)) || ndc.z > 1.0 {
+        return 1.0;
     }
 
+    // each sample is filtered over 2x2 texels by the comparison sampler already
+    let texel = 1.0 / vec2f(textureDimensions(shadowMap));
+    let depth = ndc.z - uni.shadowBias;
+    var lit = 0.0;
+    for (var y = -uni.pcfRadius; y <= uni.pcfRadius; y++) {
+        for (var x = -uni.pcfRadius; x <= uni.pcfRadius; x++) {
+            let offset = vec2f(f32(x), f32(y)) * texel;
+            lit += textureSampleCompareLevel(shadowMap, shadowSampler, uv + offset, depth);
+        }
+    }
+    let width = f32(2 * uni.pcfRadius + 1);
+    return lit / (width * width);
+}
+
+// Direction from the surface to the light and the light's color and intensity arriving there.
+struct Incoming {
+    direction: vec3f,
+    radiance: vec3f,
+};
+
+fn incoming(light: Light, position: vec3f) -> Incoming {
+    var out: Incoming;
+    if light.kind == LIGHT_DIRECTIONAL {
+        out.direction = -normalize(light.direction);
+        out.radiance = light.color * light.intensity;
+        return out;
+    }
 
-    let out_color = vec3f(v.color.rgb * light + specular);
-
-    return vec4f(pow(out_color, vec3f(2.2)), v.color.a);
-  }
\ No newline at end of file
+    let toLight = light.position - position;
+    let distance = length(toLight);
+    out.direction = toLight / distance;
+    var attenuation = 1.0;
+    if light.range > 0.0 {
+        // inverse square, windowed to reach 0 at the range
+        let window = saturate(1.0 - pow(distance / light.range, 4.0));
+        attenuation = window * window / max(distance * distance, 0.01);
+    }
+    if light.kind == LIGHT_SPOT {
+        let cosAngle = dot(-out.direction, normalize(light.direction));
+        attenuation *= smoothstep(light.cone.y, light.cone.x, cosAngle);
+    }
+    out.radiance = light.color * light.intensity * attenuation;
+    return out;
+}
+
+// The shading of the original: diffuse wrapped around to the back, so nothing is black.
+fn blinnPhong(albedo: vec3f, normal: vec3f, toView: vec3f, light: Incoming) -> vec3f {
+    let halfVector = normalize(light.direction + toView);
+    let diffuse = dot(normal, light.direction) * 0.5 + 0.5;
+    let specular = pow(max(dot(normal, halfVector), 0.0), uni.shininess);
+    return (albedo * diffuse + specular) * light.radiance;
+}
+
+// Cook-Torrance with the GGX distribution, Smith-Schlick geometry and Schlick's Fresnel.
+fn pbr(albedo: vec3f, normal: vec3f, toView: vec3f, light: Incoming) -> vec3f {
+    let nDotL = dot(normal, light.direction);
+    if nDotL <= 0.0 {
+        return vec3f(0.0);
+    }
+    let halfVector = normalize(light.direction + toView);
+    let nDotV = max(dot(normal, toView), 1e-4);
+    let nDotH = max(dot(normal, halfVector), 0.0);
+    let vDotH = max(dot(toView, halfVector), 0.0);
+
+    let roughness = clamp(uni.roughness, 0.04, 1.0);
+    let alpha2 = pow(roughness, 4.0);
+    let d = alpha2 / (PI * pow(nDotH * nDotH * (alpha2 - 1.0) + 1.0, 2.0));
+    let k = pow(roughness + 1.0, 2.0) / 8.0;
+    let g = nDotL / (nDotL * (1.0 - k) + k) * nDotV / (nDotV * (1.0 - k) + k);
+    let f0 = mix(vec3f(0.04), albedo, uni.metallic);
+    let f = f0 + (1.0 - f0) * pow(1.0 - vDotH, 5.0);
+
+    let specular = d * g * f / (4.0 * nDotL * nDotV);
+    let diffuse = (1.0 - f) * (1.0 - uni.metallic) * albedo / PI;
+    // an intensity of 1 lights a white surface facing it fully, like the Blinn-Phong path
+    return (diffuse + specular) * light.radiance * nDotL * PI;
+}
+
+@fragment
+fn myFSMain(v: VSOutput) -> @location(0) vec4f {
+    let normal = normalize(v.normal);
+    let toView = normalize(uni.viewPosition - v.worldPosition);
+    let albedo = v.color.rgb;
+
+    var outColor = vec3f(0.0);
+    if uni.shading == SHADING_PBR {
+        // a little ambient light, so the backs of the shapes aren't black
+        outColor = albedo * 0.03;
+    }
+    for (var i = 0u; i < uni.lightCount; i++) {
+        var light = incoming(lights[i], v.worldPosition);
+        if i == uni.shadowLight {
+            light.radiance *= shadow(v.worldPosition, normal);
+        }
+        if uni.shading == SHADING_PBR {
+            outColor += pbr(albedo, normal, toView, light);
+        } else {
+            outColor += blinnPhong(albedo, normal, toView, light);
+        }
+    }
+    return vec4f(pow(outColor, vec3f(2.2)), v.color.a);
+}
@@ -0,0 +1,62 @@
+//! Where animation time comes from.
+//!
+//! Demos ask a [`TimeSource`] for the current animation time once per frame instead of
+//! advancing it themselves, so the animation speed doesn't depend on the frame rate and
+//! benchmarks can replay the exact same frames.
+
+use std::time::Instant;
+
+pub enum TimeSource {
+    /// Wall-clock seconds since the first frame
+    Real { start: Option<Instant> },
+    /// Advances by `step` seconds per frame, regardless of how long the frame took
+    Fixed { step: f32, frame: u64 },
+    /// Time as a function of the frame index
+    Scripted {
+        script: Box<dyn FnMut(u64) -> f32 + Send>,
+        frame: u64,
+    },
+}
+
+impl TimeSource {
+    pub fn real() -> Self {
+        Self::Real { start: None }
+    }
+
+    pub fn fixed(step: f32) -> Self {
+        Self::Fixed { step, frame: 0 }
+    }
+
+    pub fn scripted(script: impl FnMut(u64) -> f32 + Send + 'static) -> Self {
+        Self::Scripted {
+            script: Box::new(script),
+            frame: 0,
+        }
+    }
+
+    /// Animation time of the next frame, in seconds. Call once per frame.
+    pub fn next_frame(&mut self) -> f32 {
+        match self {
+            TimeSource::Real { start } => start
+                .get_or_insert_with(Instant::now)
+                .elapsed()
+                .as_secs_f32(),
+            TimeSource::Fixed { step, frame } => {
+                let t = *frame as f32 * *step;
+                *frame += 1;
+                t
+            }
+            TimeSource::Scripted { script, frame } => {
+                let t = script(*frame);
+                *frame += 1;
+                t
+            }
+        }
+    }
+}
+
+impl Default for TimeSource {
+    fn default() -> Self {
+        Self::real()
+    }
+}
@@ -1,21 +1,25 @@
-use crate::{ColorExt, WgpuStateInitInfo};
+use crate::scene::{Frame, GpuContext, InputEvent, Scene};
+use crate::ColorExt;
 use bytemuck::checked::cast_slice;
+use std::f32::consts::{PI, TAU};
+use std::time::Duration;
 use wgpu::{
-    include_wgsl, BindGroupDescriptor, BindGroupEntry, BindingResource, Buffer, BufferDescriptor,
-    BufferUsages, Color, ColorTargetState, Device, FragmentState, Queue,
-    RenderPipeline, RenderPipelineDescriptor, VertexAttribute, VertexBufferLayout,
+    include_wgsl, BindGroup, BindGroupDescriptor, BindGroupEntry, BindingResource, Buffer,
+    BufferDescriptor, BufferUsages, Color, ColorTargetState, Device, FragmentState, Queue,
+    RenderPipeline, RenderPipelineDescriptor, TextureFormat, VertexAttribute, VertexBufferLayout,
     VertexFormat, VertexState,
 };
 
-pub struct State {
-    device: wgpu::Device,
-    queue: wgpu::Queue,
-    size: (u32, u32),
-    surface: wgpu::Surface<'static>,
-    surface_format: wgpu::TextureFormat,
+pub struct TriangleScene {
     pipeline: RenderPipeline,
     vertex_buffer: Buffer,
     uniform_buffer: Buffer,
+    bind_group: BindGroup,
+    /// Rotation in turns; one per second unless dragged
+    elapsed: f32,
+    size: (u32, u32),
+    /// Pointer spinning the triangle, and its angle around the center at the last event
+    drag: Option<(u32, f32)>,
 }
 
 #[rustfmt::skip]
@@ -36,23 +40,9 @@ static VERTICES_DATA: [f32; 15] = {
     ]
 };
 
-impl State {
-    pub async fn new(info: WgpuStateInitInfo) -> State {
-        let instance = info.instance;
-        let surface = info.surface;
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions::default())
-            .await
-            .unwrap();
-        let (device, queue) = adapter
-            .request_device(&wgpu::DeviceDescriptor::default())
-            .await
-            .unwrap();
-
-        let cap = surface.get_capabilities(&adapter);
-
-        let surface_format = cap.formats[0];
-
+impl TriangleScene {
+    pub fn new(gpu: &GpuContext, format: TextureFormat, size: (u32, u32)) -> Self {
+        let device = &gpu.device;
         let shader_module =
             device.create_shader_module(include_wgsl!("shaders/triangle-rotation.wgsl"));
 
@@ -88,7 +78,7 @@ impl State {
                 entry_point: None,
                 compilation_options: Default::default(),
                 targets: &[Some(ColorTargetState {
-                    format: surface_format.add_srgb_suffix(),
+                    format,
                     blend: None,
                     write_mask: Default::default(),
                 })],
@@ -102,28 +92,38 @@ impl State {
             cache: None,
         });
 
-        let vertex_buffer = Self::create_vertex_buffer(&device, &queue, &VERTICES_DATA);
-        let buffer = device.create_buffer(&BufferDescriptor {
+        let vertex_buffer = Self::create_vertex_buffer(device, &gpu.queue, &VERTICES_DATA);
+        let uniform_buffer = device.create_buffer(&BufferDescriptor {
             label: None,
-            size: 1 * 4,
+            size: 4,
             usage: BufferUsages::COPY_DST | BufferUsages::UNIFORM,
             mapped_at_creation: false,
         });
-        let state = State {
-            uniform_buffer: buffer,
-            device,
-            queue,
-            size: info.size,
-            surface,
-            surface_format,
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: None,
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: BindingResource::Buffer(uniform_buffer.as_entire_buffer_binding()),
+            }],
+        });
+
+        Self {
             pipeline,
             vertex_buffer,
-        };
-
-        // Configure surface for the first time
-        state.configure_surface();
+            uniform_buffer,
+            bind_group,
+            elapsed: 0.0,
+            size,
+            drag: None,
+        }
+    }
 
-        state
+    /// Counterclockwise angle of a pointer at `(x, y)` around the center of the view.
+    fn pointer_angle(&self, x: f32, y: f32) -> f32 {
+        let center_x = self.size.0 as f32 / 2.0;
+        let center_y = self.size.1 as f32 / 2.0;
+        (center_y - y).atan2(x - center_x)
     }
 
     fn create_vertex_buffer(device: &Device, queue: &Queue, data: &[f32]) -> Buffer {
@@ -136,56 +136,24 @@ impl State {
         queue.write_buffer(&buffer, 0, bytemuck::cast_slice(data));
         buffer
     }
+}
 
-    pub fn update_elapsed(&self, value: f32) {
-        self.queue
-            .write_buffer(&self.uniform_buffer, 0, cast_slice(&[value]));
-    }
-
-    pub fn configure_surface(&self) {
-        let surface_config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: self.surface_format,
-            // Request compatibility with the sRGB-format texture view we‘re going to create later.
-            view_formats: vec![self.surface_format.add_srgb_suffix()],
-            alpha_mode: wgpu::CompositeAlphaMode::Auto,
-            width: self.size.0,
-            height: self.size.1,
-            desired_maximum_frame_latency: 2,
-            present_mode: wgpu::PresentMode::AutoVsync,
-        };
-        self.surface.configure(&self.device, &surface_config);
-    }
-
-    pub fn resize(&mut self, new_size: (u32, u32)) {
-        self.size = new_size;
-
-        // reconfigure the surface
-        self.configure_surface();
+impl Scene for TriangleScene {
+    fn update(&mut self, gpu: &GpuContext, dt: Duration) {
+        // follow the finger only while dragged
+        if self.drag.is_none() {
+            self.elapsed += dt.as_secs_f32();
+        }
+        gpu.queue
+            .write_buffer(&self.uniform_buffer, 0, cast_slice(&[self.elapsed]));
     }
 
-    pub fn render(&self, redraw_callback: impl FnOnce()) {
-        // Create texture view
-        let surface_texture = self
-            .surface
-            .get_current_texture()
-            .expect("failed to acquire next swapchain texture");
-        let texture_view = surface_texture
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor {
-                // Without add_srgb_suffix() the image we will be working with
-                // might not be "gamma correct".
-                format: Some(self.surface_format.add_srgb_suffix()),
-                ..Default::default()
-            });
-
-        // Renders a gray screen
-        let mut encoder = self.device.create_command_encoder(&Default::default());
+    fn render(&mut self, _gpu: &GpuContext, frame: &mut Frame) {
         // Create the renderpass which will clear the screen.
-        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        let mut pass = frame.encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: None,
             color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: &texture_view,
+                view: frame.view,
                 depth_slice: None,
                 resolve_target: None,
                 ops: wgpu::Operations {
@@ -194,30 +162,49 @@ impl State {
                 },
             })],
             depth_stencil_attachment: None,
-            timestamp_writes: None,
+            timestamp_writes: frame.profiler.render_pass("Triangle"),
             occlusion_query_set: None,
             multiview_mask: None,
         });
 
-        let bind_group = self.device.create_bind_group(&BindGroupDescriptor {
-            label: None,
-            layout: &self.pipeline.get_bind_group_layout(0),
-            entries: &[BindGroupEntry {
-                binding: 0,
-                resource: BindingResource::Buffer(self.uniform_buffer.as_entire_buffer_binding()),
-            }],
-        });
-
         pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-        pass.set_bind_group(0, &bind_group, &[]);
+        pass.set_bind_group(0, &self.bind_group, &[]);
         pass.set_pipeline(&self.pipeline);
         pass.draw(0..3, 0..1);
+    }
 
-        drop(pass);
+    /// Dragging around the center spins the triangle.
+    fn input(&mut self, _gpu: &GpuContext, event: &InputEvent) -> bool {
+        match *event {
+            InputEvent::PointerPressed { id, x, y } if self.drag.is_none() => {
+                self.drag = Some((id, self.pointer_angle(x, y)));
+                true
+            }
+            InputEvent::PointerMoved { id, x, y } => {
+                let Some((drag_id, last_angle)) = self.drag else {
+                    return false;
+                };
+                if id != drag_id {
+                    return false;
+                }
+                let angle = self.pointer_angle(x, y);
+                // the short way around, so crossing ±π isn't a full turn
+                let delta = (angle - last_angle + PI).rem_euclid(TAU) - PI;
+                self.elapsed += delta / TAU;
+                self.drag = Some((id, angle));
+                true
+            }
+            InputEvent::PointerReleased { id, .. }
+                if self.drag.is_some_and(|(drag_id, _)| drag_id == id) =>
+            {
+                self.drag = None;
+                true
+            }
+            _ => false,
+        }
+    }
 
-        // Submit the command in the queue to execute
-        redraw_callback();
-        self.queue.submit([encoder.finish()]);
-        surface_texture.present();
+    fn resize(&mut self, _gpu: &GpuContext, size: (u32, u32)) {
+        self.size = size;
     }
 }
@@ -1,7 +1,16 @@
-use crate::WgpuStateInitInfo;
+pub mod export;
+
+use crate::blit::Blitter;
+use crate::render_scale::RenderScale;
+use crate::scene::{Frame, GpuContext, InputEvent, Scene};
 use bytemuck::{Pod, Zeroable};
-use std::iter;
-use std::time::{Duration, Instant};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Camera rotation for dragging across the full height of the view.
+const ORBIT_RADIANS_PER_VIEW: f32 = std::f32::consts::PI;
+const PITCH_LIMIT: f32 = 1.5;
+const DISTANCE_RANGE: (f32, f32) = (0.3, 5.0);
 
 // --- Uniform 数据结构 (必须符合 WGSL 的 16 字节对齐) ---
 #[repr(C)]
@@ -18,56 +27,130 @@ pub struct Uniforms {
     screen_size: [f32; 2],
     len: f32,
     padding5: f32,
+    /// Maps the pass's clip-space uv onto the full image: `uv * tile_scale + tile_offset`.
+    tile_scale: [f32; 2],
+    tile_offset: [f32; 2],
 }
 
-pub struct State {
-    surface: wgpu::Surface<'static>,
-    device: wgpu::Device,
-    queue: wgpu::Queue,
-    pub size: (u32, u32),
-    render_pipeline: wgpu::RenderPipeline,
-    uniform_buffer: wgpu::Buffer,
-    uniform_bind_group: wgpu::BindGroup,
-    elapsed: f32,
-    texture_format: wgpu::TextureFormat,
+impl Uniforms {
+    /// Uniforms for rendering the whole image of `size` pixels in one pass.
+    pub fn new(camera: &Camera, size: (u32, u32)) -> Self {
+        let ang1 = camera.yaw;
+        let ang2 = camera.pitch;
+        let len = camera.distance;
+
+        let origin = [
+            len * ang1.cos() * ang2.cos(),
+            len * ang2.sin(),
+            len * ang1.sin() * ang2.cos(),
+        ];
+        let right = [ang1.sin(), 0.0, -ang1.cos()];
+        let up = [
+            -ang2.sin() * ang1.cos(),
+            ang2.cos(),
+            -ang2.sin() * ang1.sin(),
+        ];
+        let forward = [
+            -ang1.cos() * ang2.cos(),
+            -ang2.sin(),
+            -ang1.sin() * ang2.cos(),
+        ];
+
+        // Keep the shorter side at the original FOV and widen the longer one,
+        // so pixels stay square at any aspect ratio.
+        let cx = size.0 as f32;
+        let cy = size.1 as f32;
+        let short_side = cx.min(cy);
+        let screen_size = [cx / short_side, cy / short_side];
+
+        Self {
+            origin,
+            padding1: 0.0,
+            right,
+            padding2: 0.0,
+            up,
+            padding3: 0.0,
+            forward,
+            padding4: 0.0,
+            screen_size,
+            len,
+            padding5: 0.0,
+            tile_scale: [1.0, 1.0],
+            tile_offset: [0.0, 0.0],
+        }
+    }
+
+    /// Restricts the frustum to the sub-rectangle `(x, y, width, height)` (in pixels, y down)
+    /// of an image of `size` pixels, shifted by a sub-pixel `jitter` in `[-0.5, 0.5)`.
+    pub fn with_tile(
+        mut self,
+        size: (u32, u32),
+        tile: (u32, u32, u32, u32),
+        jitter: [f32; 2],
+    ) -> Self {
+        let (w, h) = (size.0 as f32, size.1 as f32);
+        let (x, y, tw, th) = (tile.0 as f32, tile.1 as f32, tile.2 as f32, tile.3 as f32);
+        self.tile_scale = [tw / w, th / h];
+        self.tile_offset = [
+            (2.0 * x + tw) / w - 1.0 + jitter[0] * 2.0 / w,
+            1.0 - (2.0 * y + th) / h - jitter[1] * 2.0 / h,
+        ];
+        self
+    }
+}
+
+/// Orbit camera looking at the origin.
+#[derive(Copy, Clone, Debug)]
+pub struct Camera {
+    /// Horizontal angle in radians
+    pub yaw: f32,
+    /// Vertical angle in radians
+    pub pitch: f32,
+    /// Distance to the origin
+    pub distance: f32,
+}
+
+impl Camera {
+    /// The auto-rotating camera at animation time `t`.
+    pub fn auto_rotate(t: f32) -> Self {
+        Self {
+            yaw: 2.8 + t * 0.5,
+            pitch: 0.4,
+            distance: 1.6,
+        }
+    }
 }
 
-impl State {
-    pub fn configure_surface(&self) {
-        let surface_config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: self.texture_format,
-            // Request compatibility with the sRGB-format texture view we‘re going to create later.
-            view_formats: vec![self.texture_format.add_srgb_suffix()],
-            alpha_mode: wgpu::CompositeAlphaMode::Auto,
-            width: self.size.0,
-            height: self.size.1,
-            desired_maximum_frame_latency: 2,
-            present_mode: wgpu::PresentMode::AutoNoVsync,
+/// Parses `yaw,pitch,distance`.
+impl FromStr for Camera {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values = s
+            .split(',')
+            .map(|x| x.trim().parse::<f32>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        let [yaw, pitch, distance] = values[..] else {
+            return Err("expected `yaw,pitch,distance`".into());
         };
-        self.surface.configure(&self.device, &surface_config);
+        Ok(Self {
+            yaw,
+            pitch,
+            distance,
+        })
     }
+}
 
-    pub async fn new(info: WgpuStateInitInfo) -> Self {
-        let instance = info.instance;
-        let surface = info.surface;
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::HighPerformance,
-                compatible_surface: Some(&surface),
-                force_fallback_adapter: false,
-            })
-            .await
-            .unwrap();
-
-        let (device, queue) = adapter
-            .request_device(&wgpu::DeviceDescriptor::default())
-            .await
-            .unwrap();
-
-        let surface_caps = surface.get_capabilities(&adapter);
-        let texture_format = surface_caps.formats[0];
+/// The vsbm pipeline and its uniforms, independent of the render target.
+pub struct Renderer {
+    render_pipeline: wgpu::RenderPipeline,
+    uniform_buffer: wgpu::Buffer,
+    uniform_bind_group: wgpu::BindGroup,
+}
 
+impl Renderer {
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
         // --- 核心 WGSL 着色器 ---
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Shader"),
@@ -76,7 +159,7 @@ impl State {
 
         let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("Uniform Buffer"),
-            size: std::mem::size_of::<Uniforms>() as u64,
+            size: size_of::<Uniforms>() as u64,
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
@@ -126,7 +209,7 @@ impl State {
                 entry_point: Some("fs_main"),
                 compilation_options: Default::default(),
                 targets: &[Some(wgpu::ColorTargetState {
-                    format: texture_format,
+                    format,
                     blend: None,
                     write_mask: Default::default(),
                 })],
@@ -138,133 +221,223 @@ impl State {
             cache: None,
         });
 
-        let state = Self {
-            surface,
-            device,
-            queue,
-            size: info.size,
+        Self {
             render_pipeline,
             uniform_buffer,
             uniform_bind_group,
-            elapsed: 0f32,
-            texture_format,
-        };
-        state.configure_surface();
-        state
+        }
     }
 
-    pub fn resize(&mut self, new_size: (u32, u32)) {
-        self.size = new_size;
+    pub fn write_uniforms(&self, queue: &wgpu::Queue, uniforms: &Uniforms) {
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(uniforms));
+    }
+
+    /// Records a full-screen pass that clears `view` and draws the fractal onto it.
+    pub fn encode_pass(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        timestamp_writes: Option<wgpu::RenderPassTimestampWrites>,
+    ) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                depth_slice: None,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color {
+                        r: 0.07,
+                        g: 0.06,
+                        b: 0.08,
+                        a: 1.0,
+                    }),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes,
+            multiview_mask: None,
+        });
 
-        // reconfigure the surface
-        self.configure_surface();
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_bind_group(0, &self.uniform_bind_group, &[]);
+        render_pass.draw(0..6, 0..1);
     }
+}
 
-    pub fn update(&mut self) {
-        self.elapsed += 0.012;
-        let ang1 = 2.8 + self.elapsed * 0.5; // 自动旋转
-        let ang2: f32 = 0.4;
-        let len = 1.6;
+/// Offscreen texture the fractal is rendered into before being upscaled to the surface.
+struct ScaledTarget {
+    view: wgpu::TextureView,
+    blit_bind_group: wgpu::BindGroup,
+    size: (u32, u32),
+}
 
-        let origin = [
-            len * ang1.cos() * ang2.cos(),
-            len * ang2.sin(),
-            len * ang1.sin() * ang2.cos(),
-        ];
-        let right = [ang1.sin(), 0.0, -ang1.cos()];
-        let up = [
-            -ang2.sin() * ang1.cos(),
-            ang2.cos(),
-            -ang2.sin() * ang1.sin(),
-        ];
-        let forward = [
-            -ang1.cos() * ang2.cos(),
-            -ang2.sin(),
-            -ang1.sin() * ang2.cos(),
-        ];
+impl ScaledTarget {
+    fn new(
+        device: &wgpu::Device,
+        blitter: &Blitter,
+        format: wgpu::TextureFormat,
+        size: (u32, u32),
+    ) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Scaled Target"),
+            size: wgpu::Extent3d {
+                width: size.0,
+                height: size.1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&Default::default());
+        let blit_bind_group = blitter.create_bind_group(device, &view);
+        Self {
+            view,
+            blit_bind_group,
+            size,
+        }
+    }
+}
 
-        // let cx = self.size.0 as f32;
-        // let cy = self.size.1 as f32;
-        // let sx = (cx.min(cy) / cx) * (cx / cx.max(cy));
-        // let sy = (cy.min(cx) / cy) * (cy / cx.max(cy));
+pub struct VsbmScene {
+    renderer: Renderer,
+    blitter: Blitter,
+    scaled_target: ScaledTarget,
+    render_scale: RenderScale,
+    format: wgpu::TextureFormat,
+    size: (u32, u32),
+    /// Animation time in seconds
+    time: f32,
+    /// Camera the user took over from the auto-rotation, if any
+    orbit: Option<Camera>,
+    /// Pointer orbiting the camera, and its position at the last event
+    drag: Option<(u32, f32, f32)>,
+}
 
-        // 因为使用了 1:1 的 Viewport，这里 screen_size 直接给 1.0 即可
-        let uniforms = Uniforms {
-            origin,
-            padding1: 0.0,
-            right,
-            padding2: 0.0,
-            up,
-            padding3: 0.0,
-            forward,
-            padding4: 0.0,
-            screen_size: [1.0, 1.0],
-            len,
-            padding5: 0.0,
-        };
+impl VsbmScene {
+    pub fn new(gpu: &GpuContext, format: wgpu::TextureFormat, size: (u32, u32)) -> Self {
+        let renderer = Renderer::new(&gpu.device, format);
+        let blitter = Blitter::new(&gpu.device, format);
+        let render_scale = RenderScale::new();
+        let scaled_target =
+            ScaledTarget::new(&gpu.device, &blitter, format, render_scale.apply(size));
+        Self {
+            renderer,
+            blitter,
+            scaled_target,
+            render_scale,
+            format,
+            size,
+            time: 0.0,
+            orbit: None,
+            drag: None,
+        }
+    }
 
-        self.queue
-            .write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&uniforms));
+    fn camera(&self) -> Camera {
+        self.orbit.unwrap_or_else(|| Camera::auto_rotate(self.time))
     }
 
-    pub fn render(&self, before_submit_callback: impl FnOnce()) -> Result<(), wgpu::SurfaceError> {
-        let surface_texture = self.surface.get_current_texture()?;
+    /// Lets the internal resolution follow the frame time so that `fps` is held.
+    /// `None` renders at full resolution. Takes effect on the next update.
+    pub fn set_target_fps(&mut self, fps: Option<f32>) {
+        self.render_scale.set_target_fps(fps);
+    }
 
-        let texture_view = surface_texture
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor {
-                // Without add_srgb_suffix() the image we will be working with
-                // might not be "gamma correct".
-                format: Some(self.texture_format.add_srgb_suffix()),
-                ..Default::default()
-            });
+    /// Current ratio of the internal resolution to the surface size.
+    pub fn render_scale(&self) -> f32 {
+        self.render_scale.scale()
+    }
 
-        let mut encoder = self
-            .device
-            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("Render Encoder"),
-            });
+    /// Smoothed FPS the render scale is adjusted from.
+    pub fn fps(&self) -> f32 {
+        self.render_scale.fps()
+    }
 
-        {
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &texture_view,
-                    depth_slice: None,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.07,
-                            g: 0.06,
-                            b: 0.08,
-                            a: 1.0,
-                        }),
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: None,
-                occlusion_query_set: None,
-                timestamp_writes: None,
-                multiview_mask: None,
-            });
+    fn update_scaled_target(&mut self, device: &wgpu::Device) {
+        let size = self.render_scale.apply(self.size);
+        if size != self.scaled_target.size {
+            self.scaled_target = ScaledTarget::new(device, &self.blitter, self.format, size);
+        }
+    }
+}
+
+impl Scene for VsbmScene {
+    fn update(&mut self, gpu: &GpuContext, dt: Duration) {
+        self.render_scale.frame();
+        self.update_scaled_target(&gpu.device);
 
-            // --- 核心逻辑：设置居中的 1:1 正方形视口 ---
-            // let win_w = self.size.0 as f32;
-            // let win_h = self.size.1 as f32;
-            // let side = win_w.min(win_h); // 取短边
-            // let x_offset = (win_w - side) / 2.0;
-            // let y_offset = (win_h - side) / 2.0;
-            //
-            // render_pass.set_viewport(x_offset, y_offset, side, side, 0.0, 1.0);
-
-            render_pass.set_pipeline(&self.render_pipeline);
-            render_pass.set_bind_group(0, &self.uniform_bind_group, &[]);
-            render_pass.draw(0..6, 0..1);
+        self.time += dt.as_secs_f32();
+        self.renderer
+            .write_uniforms(&gpu.queue, &Uniforms::new(&self.camera(), self.size));
+    }
+
+    fn render(&mut self, _gpu: &GpuContext, frame: &mut Frame) {
+        self.renderer.encode_pass(
+            frame.encoder,
+            &self.scaled_target.view,
+            frame.profiler.render_pass("vsbm"),
+        );
+        self.blitter.encode_pass(
+            frame.encoder,
+            &self.scaled_target.blit_bind_group,
+            frame.view,
+            frame.profiler.render_pass("Blit"),
+        );
+    }
+
+    /// Dragging orbits the camera and pinching zooms; both stop the auto-rotation.
+    fn input(&mut self, _gpu: &GpuContext, event: &InputEvent) -> bool {
+        match *event {
+            InputEvent::PointerPressed { id, x, y } if self.drag.is_none() => {
+                self.drag = Some((id, x, y));
+                true
+            }
+            InputEvent::PointerMoved { id, x, y } => {
+                let Some((drag_id, last_x, last_y)) = self.drag else {
+                    return false;
+                };
+                if id != drag_id {
+                    return false;
+                }
+                let radians_per_pixel = ORBIT_RADIANS_PER_VIEW / self.size.1.max(1) as f32;
+                let mut camera = self.camera();
+                camera.yaw -= (x - last_x) * radians_per_pixel;
+                camera.pitch = (camera.pitch + (y - last_y) * radians_per_pixel)
+                    .clamp(-PITCH_LIMIT, PITCH_LIMIT);
+                self.orbit = Some(camera);
+                self.drag = Some((id, x, y));
+                true
+            }
+            InputEvent::PointerReleased { id, .. }
+                if self.drag.is_some_and(|(drag_id, ..)| drag_id == id) =>
+            {
+                self.drag = None;
+                true
+            }
+            InputEvent::Pinch { scale } if scale > 0.0 => {
+                let mut camera = self.camera();
+                camera.distance =
+                    (camera.distance / scale).clamp(DISTANCE_RANGE.0, DISTANCE_RANGE.1);
+                self.orbit = Some(camera);
+                true
+            }
+            _ => false,
         }
+    }
+
+    fn resize(&mut self, gpu: &GpuContext, size: (u32, u32)) {
+        self.size = size;
+        self.update_scaled_target(&gpu.device);
+    }
 
-        before_submit_callback();
-        self.queue.submit(iter::once(encoder.finish()));
-        surface_texture.present();
-        Ok(())
+    fn status(&self) -> Option<String> {
+        Some(format!("render scale: {:.2}", self.render_scale()))
     }
 }
@@ -0,0 +1,251 @@
+//! Tiled offscreen rendering of vsbm still images.
+//!
+//! The image is split into tiles no larger than the adapter's max texture dimension.
+//! Each tile is rendered with its own sub-frustum (see [`Uniforms::with_tile`]),
+//! optionally supersampled by accumulating jittered passes, and streamed row by row
+//! into a PNG, so the output size is only limited by the PNG format itself.
+
+use crate::vsbm::{Camera, Renderer, Uniforms};
+use crate::wgpu_instance_with_env_backend;
+use anyhow::anyhow;
+use half::f16;
+use log::info;
+use std::io::Write;
+use tokio::sync::oneshot;
+use wgpu::wgt::PollType;
+
+/// Float target, so jittered samples can be averaged in linear space.
+/// (`Rgba32Float` isn't renderable on downlevel backends.)
+const TILE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+const TILE_BYTES_PER_PIXEL: u32 = 8;
+
+pub struct ExportOptions {
+    /// Output image size in pixels
+    pub size: (u32, u32),
+    pub camera: Camera,
+    /// Requested tile edge length; clamped to the adapter's limits
+    pub tile_size: u32,
+    /// Jittered passes accumulated per tile
+    pub samples: u32,
+}
+
+struct Exporter {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    renderer: Renderer,
+    tile_texture: wgpu::Texture,
+    readback_buffer: wgpu::Buffer,
+    tile_size: u32,
+    padded_bytes_per_row: u32,
+}
+
+impl Exporter {
+    async fn new(tile_size: u32) -> anyhow::Result<Self> {
+        let instance = wgpu_instance_with_env_backend();
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::HighPerformance,
+                compatible_surface: None,
+                force_fallback_adapter: false,
+            })
+            .await?;
+        info!("Adapter: {:?}", adapter.get_info());
+        let (device, queue) = adapter
+            .request_device(&wgpu::DeviceDescriptor {
+                required_limits: adapter.limits(),
+                ..Default::default()
+            })
+            .await?;
+
+        let tile_size = tile_size.clamp(1, device.limits().max_texture_dimension_2d);
+        let padded_bytes_per_row =
+            (tile_size * TILE_BYTES_PER_PIXEL).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+
+        let renderer = Renderer::new(&device, TILE_FORMAT);
+        let tile_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Export Tile"),
+            size: wgpu::Extent3d {
+                width: tile_size,
+                height: tile_size,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: TILE_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Export Readback"),
+            size: padded_bytes_per_row as u64 * tile_size as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Ok(Self {
+            device,
+            queue,
+            renderer,
+            tile_texture,
+            readback_buffer,
+            tile_size,
+            padded_bytes_per_row,
+        })
+    }
+
+    /// Renders one jittered pass of the tile and adds its linear RGB into `accum`
+    /// (`tile_size * tile_size * 3` floats).
+    async fn accumulate_pass(&self, uniforms: &Uniforms, accum: &mut [f32]) -> anyhow::Result<()> {
+        self.renderer.write_uniforms(&self.queue, uniforms);
+
+        let view = self.tile_texture.create_view(&Default::default());
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        self.renderer.encode_pass(&mut encoder, &view, None);
+        encoder.copy_texture_to_buffer(
+            self.tile_texture.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &self.readback_buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(self.padded_bytes_per_row),
+                    rows_per_image: None,
+                },
+            },
+            self.tile_texture.size(),
+        );
+        self.queue.submit([encoder.finish()]);
+
+        let (tx, rx) = oneshot::channel();
+        self.readback_buffer
+            .map_async(wgpu::MapMode::Read, .., |e| {
+                tx.send(e).unwrap();
+            });
+        self.device.poll(PollType::Wait {
+            submission_index: None,
+            timeout: None,
+        })?;
+        rx.await??;
+
+        {
+            let mapped = self.readback_buffer.get_mapped_range(..);
+            let ts = self.tile_size as usize;
+            for (y, row) in mapped
+                .chunks_exact(self.padded_bytes_per_row as usize)
+                .enumerate()
+            {
+                let texels: &[u16] =
+                    bytemuck::cast_slice(&row[..ts * TILE_BYTES_PER_PIXEL as usize]);
+                for (x, texel) in texels.chunks_exact(4).enumerate() {
+                    let dst = &mut accum[(y * ts + x) * 3..][..3];
+                    for (d, &t) in dst.iter_mut().zip(texel) {
+                        *d += f16::from_bits(t).to_f32();
+                    }
+                }
+            }
+        }
+        self.readback_buffer.unmap();
+        Ok(())
+    }
+}
+
+/// Renders a still image with `options` and writes it as an RGB PNG to `output`.
+pub async fn export_png(options: &ExportOptions, output: impl Write) -> anyhow::Result<()> {
+    let (width, height) = options.size;
+    if width == 0 || height == 0 {
+        return Err(anyhow!("Image size must be non-zero"));
+    }
+    // bounds every offset into the strips below
+    (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|x| x.checked_mul(3))
+        .ok_or_else(|| anyhow!("Image size {}x{} is too large", width, height))?;
+    let samples = options.samples.max(1);
+    let exporter = Exporter::new(options.tile_size).await?;
+    let ts = exporter.tile_size;
+    let tiles = (width.div_ceil(ts), height.div_ceil(ts));
+    info!(
+        "Exporting {}x{} in {}x{} tiles of {}px, {} sample(s) per pixel",
+        width, height, tiles.0, tiles.1, ts, samples
+    );
+
+    let mut encoder = png::Encoder::new(output, width, height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    let mut stream = writer.stream_writer()?;
+
+    let base_uniforms = Uniforms::new(&options.camera, options.size);
+    let ts_usize = ts as usize;
+    let mut accum = vec![0f32; ts_usize * ts_usize * 3];
+    for tile_y in 0..tiles.1 {
+        let y = tile_y * ts;
+        let strip_height = ts.min(height - y);
+        let mut strip = vec![0u8; width as usize * strip_height as usize * 3];
+
+        for tile_x in 0..tiles.0 {
+            let x = tile_x * ts;
+            let tile_width = ts.min(width - x);
+
+            accum.fill(0.0);
+            for sample in 0..samples {
+                let uniforms =
+                    base_uniforms.with_tile(options.size, (x, y, ts, ts), sample_jitter(sample));
+                exporter.accumulate_pass(&uniforms, &mut accum).await?;
+            }
+
+            for row in 0..strip_height as usize {
+                for col in 0..tile_width as usize {
+                    let src = &accum[(row * ts_usize + col) * 3..][..3];
+                    let dst = &mut strip[(row * width as usize + x as usize + col) * 3..][..3];
+                    for (d, &s) in dst.iter_mut().zip(src) {
+                        *d = linear_to_srgb8(s / samples as f32);
+                    }
+                }
+            }
+            info!(
+                "Tile ({}, {}) of ({}, {}) done",
+                tile_x + 1,
+                tile_y + 1,
+                tiles.0,
+                tiles.1
+            );
+        }
+
+        stream.write_all(&strip)?;
+    }
+
+    stream.finish()?;
+    writer.finish()?;
+    Ok(())
+}
+
+/// Sub-pixel offset of the `n`th sample; the first one is the pixel center,
+/// the rest follow the Halton (2, 3) sequence.
+fn sample_jitter(n: u32) -> [f32; 2] {
+    if n == 0 {
+        return [0.0, 0.0];
+    }
+    [halton(n, 2) - 0.5, halton(n, 3) - 0.5]
+}
+
+fn halton(mut index: u32, base: u32) -> f32 {
+    let mut f = 1.0;
+    let mut r = 0.0;
+    while index > 0 {
+        f /= base as f32;
+        r += f * (index % base) as f32;
+        index /= base;
+    }
+    r
+}
+
+fn linear_to_srgb8(x: f32) -> u8 {
+    let x = x.clamp(0.0, 1.0);
+    let srgb = if x <= 0.0031308 {
+        x * 12.92
+    } else {
+        1.055 * x.powf(1.0 / 2.4) - 0.055
+    };
+    (srgb * 255.0).round() as u8
+}
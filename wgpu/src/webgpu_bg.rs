@@ -0,0 +1,1137 @@
+//! Port of the background animation of the WebGPU Fundamentals site.
+//!
+//! The instance transforms are computed by a compute pass, or on the CPU to compare, so the
+//! instance count can go far beyond the original thousand. The meshes keep their vertex colors
+//! but are lit by a list of [`Light`]s, with the original Blinn-Phong or PBR [`Shading`].
+//! The first directional light casts shadows through a shadow map, see [`ShadowSettings`].
+
+use crate::mesh::{Mesh, Vertex};
+use crate::overlay::Overlay;
+use crate::readback::Readback;
+use crate::scene::{Frame, GpuContext, InputEvent, Key, Scene};
+use crate::ValueEnumExt;
+use clap::ValueEnum;
+use glam::{Mat4, Vec3};
+use log::warn;
+use std::time::Duration;
+use wgpu::util::{DeviceExt, DrawIndirectArgs};
+use wgpu::wgt::PollType;
+use wgpu::TextureFormat;
+
+/// Instances of the original animation.
+pub const DEFAULT_INSTANCES: u32 = 1000;
+/// Transforms of this many instances fit in the default `max_storage_buffer_binding_size`.
+pub const MAX_INSTANCES: u32 = 2_000_000;
+/// Must match `cs_animate` in webgpu-bg-animate.wgsl and `cs_cull` in webgpu-bg-cull.wgsl.
+const WORKGROUP_SIZE: u32 = 64;
+/// Lights the storage buffer has room for.
+pub const MAX_LIGHTS: usize = 16;
+/// Largest shadow map `max_texture_dimension_2d` allows by default.
+pub const MAX_SHADOW_MAP_SIZE: u32 = 8192;
+const SHADOW_FORMAT: TextureFormat = TextureFormat::Depth32Float;
+/// The legacy dump and the meshes [`Mesh`] mirrors into the left-handed space wind the faces on
+/// their outside clockwise on screen.
+const FRONT_FACE: wgpu::FrontFace = wgpu::FrontFace::Cw;
+const OVERLAY_BACKGROUND: [f32; 4] = [0.0, 0.0, 0.0, 0.6];
+const OVERLAY_TEXT: [f32; 4] = [0.9, 0.9, 0.9, 1.0];
+
+/// The mesh of the original animation.
+pub fn default_mesh() -> Mesh {
+    Mesh::parse_legacy(include_str!("../data/webgpu-bg-data.txt")).unwrap()
+}
+
+// 对应 WGSL 中的 struct Uniforms
+// 注意对齐：vec3f 在 uniform 中占 16 字节
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct Uniforms {
+    view_projection: [f32; 16],
+    view_position: [f32; 3],
+    light_count: u32, // 刚好补位
+    shading: u32,
+    shininess: f32,
+    metallic: f32,
+    roughness: f32,
+    light_view_projection: [f32; 16],
+    shadow_light: u32,
+    shadow_bias: f32,
+    normal_bias: f32,
+    pcf_radius: i32,
+}
+
+/// `Light` in webgpu-bg.wgsl.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct GpuLight {
+    position: [f32; 3],
+    kind: u32,
+    direction: [f32; 3],
+    intensity: f32,
+    color: [f32; 3],
+    range: f32,
+    /// Cosines of the inner and outer half angles
+    cone: [f32; 2],
+    _pad: [f32; 2],
+}
+
+// 对应 WGSL 中的 struct Inst
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct InstanceData {
+    matrix: [f32; 16],
+}
+
+/// `Params` in webgpu-bg-animate.wgsl.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct AnimateParams {
+    time: f32,
+    count: u32,
+}
+
+/// `Params` in webgpu-bg-cull.wgsl.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct CullParams {
+    planes: [[f32; 4]; 6],
+    radius: f32,
+    count: u32,
+    enabled: u32,
+    _pad: u32,
+}
+
+/// Planes of the frustum of `view_projection`, for clip-space depth from 0 to 1, facing inward
+/// and normalized.
+fn frustum_planes(view_projection: Mat4) -> [[f32; 4]; 6] {
+    let [x, y, z, w] = [0, 1, 2, 3].map(|i| view_projection.row(i));
+    [w + x, w - x, w + y, w - y, z, w - z].map(|plane| (plane / plane.truncate().length()).into())
+}
+
+/// Orthographic projection along `direction` of a sphere of `radius` around the origin, with
+/// the sphere's depth between 1/3 and 1.
+fn light_view_projection(direction: Vec3, radius: f32) -> Mat4 {
+    let direction = direction.normalize();
+    let up = if direction.y.abs() > 0.99 { Vec3::Z } else { Vec3::Y };
+    let view = Mat4::look_to_lh(-direction * radius * 2.0, direction, up);
+    Mat4::orthographic_lh(-radius, radius, -radius, radius, radius, radius * 3.0) * view
+}
+
+/// `InstanceInfo` in webgpu-bg-animate.wgsl.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct InstanceInfo {
+    offset: [f32; 3],
+    /// Fraction of a turn the instance is ahead of the first one
+    time_offset: f32,
+}
+
+impl InstanceInfo {
+    /// Same as `cs_animate` in webgpu-bg-animate.wgsl.
+    fn matrix(&self, time: f32) -> Mat4 {
+        let t = time * 0.1 + self.time_offset * std::f32::consts::PI * 2.0;
+        Mat4::from_translation(self.offset.into())
+            * Mat4::from_rotation_z(t)
+            * Mat4::from_rotation_x(t * 0.9)
+            * Mat4::from_scale(Vec3::splat(3.0))
+    }
+}
+
+/// Where the instance transforms are computed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Animation {
+    /// On the CPU, uploaded with `write_buffer` every frame
+    Cpu,
+    /// By a compute pass, straight into the storage buffer the vertex shader reads
+    Compute,
+}
+
+/// A light source. Its color is linear, and the intensity scales it.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Light {
+    pub kind: LightKind,
+    pub color: [f32; 3],
+    pub intensity: f32,
+}
+
+/// Where a [`Light`] shines from. `range` is the distance at which it has faded out, or 0 for
+/// a light that doesn't fall off with distance, like the one of the original.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LightKind {
+    /// Infinitely far away, shining along `direction`
+    Directional { direction: Vec3 },
+    /// Shining from `position` in every direction
+    Point { position: Vec3, range: f32 },
+    /// Shining from `position` in a cone around `direction`, fading out from `inner_angle` to
+    /// `outer_angle` off its axis, in radians
+    Spot {
+        position: Vec3,
+        direction: Vec3,
+        range: f32,
+        inner_angle: f32,
+        outer_angle: f32,
+    },
+}
+
+impl Light {
+    /// The single white light of the original, without falloff.
+    pub fn point(position: Vec3) -> Self {
+        Self {
+            kind: LightKind::Point { position, range: 0.0 },
+            color: [1.0; 3],
+            intensity: 1.0,
+        }
+    }
+
+    fn to_gpu(self) -> GpuLight {
+        let (kind, position, direction, range, cone) = match self.kind {
+            LightKind::Directional { direction } => (0, Vec3::ZERO, direction, 0.0, [1.0; 2]),
+            LightKind::Point { position, range } => (1, position, Vec3::NEG_Z, range, [1.0; 2]),
+            LightKind::Spot { position, direction, range, inner_angle, outer_angle } => {
+                (2, position, direction, range, [inner_angle.cos(), outer_angle.cos()])
+            }
+        };
+        GpuLight {
+            position: position.into(),
+            kind,
+            direction: direction.into(),
+            intensity: self.intensity,
+            color: self.color,
+            range,
+            cone,
+            _pad: [0.0; 2],
+        }
+    }
+}
+
+/// Sets of lights to start from.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum LightRig {
+    /// The white point light of the original
+    Original,
+    /// A warm directional light, a pink point light and a blue spot light
+    Colored,
+    /// A white directional light from the side, casting long shadows
+    Sun,
+}
+
+impl LightRig {
+    pub fn lights(self) -> Vec<Light> {
+        match self {
+            Self::Original => vec![Light::point(Vec3::new(2.0, 3.0, 6.0))],
+            Self::Colored => vec![
+                Light {
+                    kind: LightKind::Directional { direction: Vec3::new(-0.3, -0.5, -1.0) },
+                    color: [1.0, 0.85, 0.7],
+                    intensity: 0.6,
+                },
+                Light {
+                    kind: LightKind::Point { position: Vec3::new(-1.5, -1.0, 1.5), range: 4.0 },
+                    color: [1.0, 0.3, 0.6],
+                    intensity: 3.0,
+                },
+                Light {
+                    kind: LightKind::Spot {
+                        position: Vec3::new(0.5, 2.5, 3.0),
+                        direction: Vec3::new(-0.5, -2.5, -3.0),
+                        range: 8.0,
+                        inner_angle: 10.0f32.to_radians(),
+                        outer_angle: 20.0f32.to_radians(),
+                    },
+                    color: [0.2, 0.4, 1.0],
+                    intensity: 12.0,
+                },
+            ],
+            Self::Sun => vec![Light {
+                kind: LightKind::Directional { direction: Vec3::new(-1.0, -0.6, -0.5) },
+                color: [1.0; 3],
+                intensity: 1.0,
+            }],
+        }
+    }
+}
+
+/// How the surfaces reflect the lights.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Shading {
+    /// Blinn-Phong with the diffuse light wrapped around to the back, as in the original
+    #[value(name = "phong")]
+    BlinnPhong,
+    /// Metallic-roughness PBR, with a GGX specular lobe
+    Pbr,
+}
+
+/// The parts of the surface's material that don't come from the vertex colors.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Material {
+    /// Blinn-Phong specular exponent
+    pub shininess: f32,
+    /// PBR metalness, from 0 for dielectrics to 1 for metals
+    pub metallic: f32,
+    /// PBR perceptual roughness, from 0 for mirrors to 1
+    pub roughness: f32,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self { shininess: 150.0, metallic: 0.0, roughness: 0.4 }
+    }
+}
+
+/// The shadow map of the first directional light.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ShadowSettings {
+    pub enabled: bool,
+    /// Width and height of the shadow map, at most [`MAX_SHADOW_MAP_SIZE`]
+    pub resolution: u32,
+    /// Offset of the compared depth against shadow acne, in world units
+    pub bias: f32,
+    /// Offset of the looked up position along the surface normal, in world units
+    pub normal_bias: f32,
+    /// Texels around the center the PCF kernel reaches out; 0 takes a single sample
+    pub pcf_radius: u32,
+}
+
+impl Default for ShadowSettings {
+    fn default() -> Self {
+        Self { enabled: true, resolution: 2048, bias: 0.01, normal_bias: 0.01, pcf_radius: 1 }
+    }
+}
+
+// ---------------------------------------------------------
+// 渲染状态
+// ---------------------------------------------------------
+pub struct WebgpuBgScene {
+    size: (u32, u32),
+    format: TextureFormat,
+
+    pipeline: wgpu::RenderPipeline,
+    vertex_buffer: wgpu::Buffer,
+    num_vertices: u32,
+
+    uniform_buffer: wgpu::Buffer,
+    storage_buffer: wgpu::Buffer,
+    light_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+
+    lights: Vec<Light>,
+    /// The rig `lights` came from, `None` for lights set with `set_lights`
+    light_rig: Option<LightRig>,
+    shading: Shading,
+    material: Material,
+
+    shadow: ShadowSettings,
+    /// Index of the light casting shadows in this frame
+    shadow_light: Option<usize>,
+    shadow_pipeline: wgpu::RenderPipeline,
+    shadow_bind_group_layout: wgpu::BindGroupLayout,
+    shadow_sampler: wgpu::Sampler,
+    /// Size of `shadow_view`, which is recreated when the resolution changes
+    shadow_map_size: u32,
+    shadow_view: wgpu::TextureView,
+    shadow_bind_group: wgpu::BindGroup,
+
+    animation: Animation,
+    animate_pipeline: wgpu::ComputePipeline,
+    animate_params_buffer: wgpu::Buffer,
+    animate_bind_group: wgpu::BindGroup,
+
+    culling: bool,
+    /// Bounding sphere of the mesh around its origin
+    mesh_radius: f32,
+    cull_pipeline: wgpu::ComputePipeline,
+    cull_params_buffer: wgpu::Buffer,
+    cull_bind_group: wgpu::BindGroup,
+    draw_args_buffer: wgpu::Buffer,
+    count_readback_buffer: wgpu::Buffer,
+    count_readback: Readback,
+    /// Instances drawn a few frames ago, once read back
+    visible_count: Option<u32>,
+
+    overlay: Overlay,
+    show_overlay: bool,
+
+    msaa_view: wgpu::TextureView,
+    depth_view: wgpu::TextureView,
+
+    /// Animation time in seconds
+    time: f32,
+    instances: Vec<InstanceInfo>,
+}
+
+impl WebgpuBgScene {
+    pub fn new(gpu: &GpuContext, format: TextureFormat, size: (u32, u32)) -> Self {
+        Self::with_mesh(gpu, format, size, &default_mesh(), DEFAULT_INSTANCES, Animation::Compute)
+    }
+
+    /// `instance_count` copies of `mesh` instead of the default ones, at most [`MAX_INSTANCES`].
+    /// The mesh should be about as large as [`default_mesh`], see [`Mesh::fit`].
+    pub fn with_mesh(
+        gpu: &GpuContext,
+        format: TextureFormat,
+        size: (u32, u32),
+        mesh: &Mesh,
+        instance_count: u32,
+        animation: Animation,
+    ) -> Self {
+        let device = &gpu.device;
+
+        // --- Shader ---
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shaders/webgpu-bg.wgsl").into()),
+        });
+
+        // --- Data Setup ---
+        let num_vertices = mesh.vertices.len() as u32;
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Vertex Buffer"),
+            contents: bytemuck::cast_slice(&mesh.vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        // --- Instances ---
+        let num_instances = instance_count.clamp(1, MAX_INSTANCES) as usize;
+        let g_angle = std::f32::consts::PI * (3.0 - 5.0f32.sqrt());
+        let mut instances = Vec::with_capacity(num_instances);
+
+        for i in 0..num_instances {
+            let i_f = i as f32;
+            let t = i_f * g_angle;
+            let r = (i_f / num_instances as f32).sqrt() * 2.0;
+            let c = t.cos();
+            let s = t.sin();
+
+            instances.push(InstanceInfo {
+                offset: [c * r, s * r, 0.0],
+                time_offset: i_f / num_instances as f32,
+            });
+        }
+
+        // written by `update` or the compute pass before every draw
+        let storage_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Storage Buffer"),
+            size: (num_instances * std::mem::size_of::<InstanceData>()) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let info_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Instance Info Buffer"),
+            contents: bytemuck::cast_slice(&instances),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let animate_params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Animate Params Buffer"),
+            size: std::mem::size_of::<AnimateParams>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let animate_module = device.create_shader_module(wgpu::include_wgsl!("shaders/webgpu-bg-animate.wgsl"));
+        let animate_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Animate Pipeline"),
+            layout: None,
+            module: &animate_module,
+            entry_point: Some("cs_animate"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+        let animate_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &animate_pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: animate_params_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: info_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: storage_buffer.as_entire_binding() },
+            ],
+            label: None,
+        });
+
+        // --- Culling ---
+        let visible_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Visible Instances Buffer"),
+            size: (num_instances * std::mem::size_of::<u32>()) as u64,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+        let draw_args_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Draw Args Buffer"),
+            size: std::mem::size_of::<DrawIndirectArgs>() as u64,
+            usage: wgpu::BufferUsages::INDIRECT | wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let count_readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Visible Count Readback"),
+            size: std::mem::size_of::<u32>() as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let cull_params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Cull Params Buffer"),
+            size: std::mem::size_of::<CullParams>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let cull_module = device.create_shader_module(wgpu::include_wgsl!("shaders/webgpu-bg-cull.wgsl"));
+        let cull_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Cull Pipeline"),
+            layout: None,
+            module: &cull_module,
+            entry_point: Some("cs_cull"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+        let cull_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &cull_pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: cull_params_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: storage_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: visible_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 3, resource: draw_args_buffer.as_entire_binding() },
+            ],
+            label: None,
+        });
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Uniform Buffer"),
+            size: std::mem::size_of::<Uniforms>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let light_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Light Buffer"),
+            size: (MAX_LIGHTS * std::mem::size_of::<GpuLight>()) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        // --- Pipeline ---
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer { ty: wgpu::BufferBindingType::Uniform, has_dynamic_offset: false, min_binding_size: None },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer { ty: wgpu::BufferBindingType::Storage { read_only: true }, has_dynamic_offset: false, min_binding_size: None },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer { ty: wgpu::BufferBindingType::Storage { read_only: true }, has_dynamic_offset: false, min_binding_size: None },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer { ty: wgpu::BufferBindingType::Storage { read_only: true }, has_dynamic_offset: false, min_binding_size: None },
+                    count: None,
+                },
+            ],
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: uniform_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: storage_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: visible_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 3, resource: light_buffer.as_entire_binding() },
+            ],
+            label: None,
+        });
+
+        // --- Shadows ---
+        let shadow_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Shadow Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture { sample_type: wgpu::TextureSampleType::Depth, view_dimension: wgpu::TextureViewDimension::D2, multisampled: false },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison),
+                    count: None,
+                },
+            ],
+        });
+        let shadow_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Shadow Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            compare: Some(wgpu::CompareFunction::LessEqual),
+            ..Default::default()
+        });
+        let shadow = ShadowSettings::default();
+        let (shadow_view, shadow_bind_group) = Self::create_shadow_map(device, &shadow_bind_group_layout, &shadow_sampler, shadow.resolution);
+
+        let shadow_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[&bind_group_layout],
+            immediate_size: 0,
+        });
+        let shadow_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Shadow Pipeline"),
+            layout: Some(&shadow_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("shadowVSMain"),
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<Vertex>() as u64,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &wgpu::vertex_attr_array![0 => Float32x3],
+                }],
+                compilation_options: Default::default(),
+            },
+            fragment: None,
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                front_face: FRONT_FACE,
+                cull_mode: Some(wgpu::Face::Back),
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: SHADOW_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            cache: None,
+            multiview_mask: None,
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[&bind_group_layout, &shadow_bind_group_layout],
+            immediate_size: 0,
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Render Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("myVSMain"),
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<Vertex>() as u64,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3, 2 => Float32x4],
+                }],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("myFSMain"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                front_face: FRONT_FACE,
+                cull_mode: Some(wgpu::Face::Back),
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth24Plus,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: 4,
+                ..Default::default()
+            },
+            cache: None,
+            multiview_mask: None,
+        });
+
+        let (msaa_view, depth_view) = Self::create_textures(device, format, size);
+
+        Self {
+            size, format,
+            pipeline, vertex_buffer, num_vertices,
+            uniform_buffer, storage_buffer, light_buffer, bind_group,
+            lights: LightRig::Original.lights(),
+            light_rig: Some(LightRig::Original),
+            shading: Shading::BlinnPhong,
+            material: Material::default(),
+            shadow,
+            shadow_light: None,
+            shadow_pipeline, shadow_bind_group_layout, shadow_sampler,
+            shadow_map_size: shadow.resolution,
+            shadow_view, shadow_bind_group,
+            animation, animate_pipeline, animate_params_buffer, animate_bind_group,
+            culling: true,
+            mesh_radius: mesh.radius(),
+            cull_pipeline, cull_params_buffer, cull_bind_group,
+            draw_args_buffer, count_readback_buffer,
+            count_readback: Readback::Free,
+            visible_count: None,
+            overlay: Overlay::new(device, &gpu.queue, format),
+            show_overlay: false,
+            msaa_view, depth_view,
+            time: 0.0,
+            instances,
+        }
+    }
+
+    pub fn animation(&self) -> Animation {
+        self.animation
+    }
+
+    pub fn set_animation(&mut self, animation: Animation) {
+        self.animation = animation;
+    }
+
+    /// Replaces the lights, keeping the first [`MAX_LIGHTS`].
+    pub fn set_lights(&mut self, lights: &[Light]) {
+        if lights.len() > MAX_LIGHTS {
+            warn!("Only {} of {} lights are used", MAX_LIGHTS, lights.len());
+        }
+        self.lights = lights.iter().take(MAX_LIGHTS).copied().collect();
+        self.light_rig = None;
+    }
+
+    pub fn set_light_rig(&mut self, rig: LightRig) {
+        self.lights = rig.lights();
+        self.light_rig = Some(rig);
+    }
+
+    pub fn shading(&self) -> Shading {
+        self.shading
+    }
+
+    pub fn set_shading(&mut self, shading: Shading) {
+        self.shading = shading;
+    }
+
+    pub fn material(&self) -> Material {
+        self.material
+    }
+
+    pub fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+
+    pub fn shadow_settings(&self) -> ShadowSettings {
+        self.shadow
+    }
+
+    /// Takes effect with the next frame, which recreates the shadow map if the resolution
+    /// changed.
+    pub fn set_shadow_settings(&mut self, shadow: ShadowSettings) {
+        self.shadow = ShadowSettings {
+            resolution: shadow.resolution.clamp(1, MAX_SHADOW_MAP_SIZE),
+            ..shadow
+        };
+    }
+
+    fn light_rig_name(&self) -> String {
+        self.light_rig.map_or("custom".into(), |x| x.name())
+    }
+
+    /// Skips drawing the instances outside of the view, which is on by default.
+    pub fn set_culling(&mut self, culling: bool) {
+        self.culling = culling;
+    }
+
+    /// Shows the number of visible and total instances on top.
+    pub fn set_overlay_visible(&mut self, visible: bool) {
+        self.show_overlay = visible;
+    }
+
+    /// Collects the visible instance count of an earlier frame if it's ready, and starts
+    /// reading back the one copied by the last frame.
+    fn poll_visible_count(&mut self, device: &wgpu::Device) {
+        if let Readback::Mapping(_) = self.count_readback {
+            let _ = device.poll(PollType::Poll);
+            let count = self
+                .count_readback
+                .take(&self.count_readback_buffer, |bytes| *bytemuck::from_bytes::<u32>(bytes));
+            if let Some(count) = count {
+                self.visible_count = Some(count);
+            }
+        }
+        self.count_readback.map(&self.count_readback_buffer);
+    }
+
+    fn visible_count_text(&self) -> String {
+        match self.visible_count {
+            Some(count) => count.to_string(),
+            None => "-".into(),
+        }
+    }
+
+    fn create_textures(device: &wgpu::Device, format: TextureFormat, size: (u32, u32)) -> (wgpu::TextureView, wgpu::TextureView) {
+        let msaa_tex = device.create_texture(&wgpu::TextureDescriptor {
+            size: wgpu::Extent3d { width: size.0, height: size.1, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 4,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            label: Some("MSAA Texture"),
+            view_formats: &[],
+        });
+        let depth_tex = device.create_texture(&wgpu::TextureDescriptor {
+            size: wgpu::Extent3d { width: size.0, height: size.1, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 4,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Depth24Plus,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            label: Some("Depth Texture"),
+            view_formats: &[],
+        });
+        (msaa_tex.create_view(&Default::default()), depth_tex.create_view(&Default::default()))
+    }
+
+    fn create_shadow_map(device: &wgpu::Device, layout: &wgpu::BindGroupLayout, sampler: &wgpu::Sampler, size: u32) -> (wgpu::TextureView, wgpu::BindGroup) {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            size: wgpu::Extent3d { width: size, height: size, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: SHADOW_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            label: Some("Shadow Map"),
+            view_formats: &[],
+        });
+        let view = texture.create_view(&Default::default());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: wgpu::BindingResource::TextureView(&view) },
+                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::Sampler(sampler) },
+            ],
+            label: Some("Shadow Bind Group"),
+        });
+        (view, bind_group)
+    }
+
+    fn shadow_text(&self) -> String {
+        match (self.shadow.enabled, self.shadow_light) {
+            (false, _) => "off".into(),
+            (true, None) => "on (no directional light)".into(),
+            (true, Some(_)) => format!(
+                "on {} bias {:.3} normal {:.3} pcf {}",
+                self.shadow.resolution, self.shadow.bias, self.shadow.normal_bias, self.shadow.pcf_radius,
+            ),
+        }
+    }
+
+}
+
+impl Scene for WebgpuBgScene {
+    fn update(&mut self, gpu: &GpuContext, dt: Duration) {
+        self.time += dt.as_secs_f32();
+        let time = self.time;
+
+        // 1. 更新 Uniforms
+        let aspect = self.size.0 as f32 / self.size.1 as f32;
+        let fov_y = 30.0f32.to_radians();
+        let projection = Mat4::perspective_lh(fov_y, aspect, 0.01, 50.0);
+
+        let half_size = 1.5;
+        let fov_x = 2.0 * ((fov_y * 0.5).tan() * aspect).atan();
+        let dist_x = half_size / (fov_x * 0.5).tan();
+        let dist_y = half_size / (fov_y * 0.5).tan();
+        let eye = Vec3::new(0.0, 0.0, dist_x.min(dist_y));
+
+        let view = Mat4::look_at_lh(eye, Vec3::ZERO, Vec3::Y);
+        let view_proj = projection * view;
+
+        // 阴影：第一个平行光，正交投影罩住所有实例
+        self.shadow_light = self.lights.iter()
+            .position(|x| matches!(x.kind, LightKind::Directional { .. }))
+            .filter(|_| self.shadow.enabled);
+        // the instances are at most 2 from the center and scaled by 3
+        let scene_radius = 2.0 + self.mesh_radius * 3.0;
+        let light_view_proj = match self.shadow_light.map(|i| self.lights[i].kind) {
+            Some(LightKind::Directional { direction }) => light_view_projection(direction, scene_radius),
+            _ => Mat4::IDENTITY,
+        };
+        if self.shadow_map_size != self.shadow.resolution {
+            let (view, bind_group) = Self::create_shadow_map(&gpu.device, &self.shadow_bind_group_layout, &self.shadow_sampler, self.shadow.resolution);
+            self.shadow_view = view;
+            self.shadow_bind_group = bind_group;
+            self.shadow_map_size = self.shadow.resolution;
+        }
+
+        let uniforms = Uniforms {
+            view_projection: view_proj.to_cols_array(),
+            view_position: eye.into(),
+            light_count: self.lights.len() as u32,
+            shading: self.shading as u32,
+            shininess: self.material.shininess,
+            metallic: self.material.metallic,
+            roughness: self.material.roughness,
+            light_view_projection: light_view_proj.to_cols_array(),
+            shadow_light: self.shadow_light.map_or(u32::MAX, |i| i as u32),
+            // the depth of the shadow map spans twice the radius
+            shadow_bias: self.shadow.bias / (2.0 * scene_radius),
+            normal_bias: self.shadow.normal_bias,
+            pcf_radius: self.shadow.pcf_radius as i32,
+        };
+        gpu.queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&uniforms));
+        if !self.lights.is_empty() {
+            let lights = self.lights.iter().map(|x| x.to_gpu()).collect::<Vec<_>>();
+            gpu.queue.write_buffer(&self.light_buffer, 0, bytemuck::cast_slice(&lights));
+        }
+
+        // 2. 更新 Storage Buffer (Instances)
+        match self.animation {
+            Animation::Cpu => {
+                let matrix_data = self.instances.iter()
+                    .map(|info| InstanceData { matrix: info.matrix(time).to_cols_array() })
+                    .collect::<Vec<_>>();
+                gpu.queue.write_buffer(&self.storage_buffer, 0, bytemuck::cast_slice(&matrix_data));
+            }
+            Animation::Compute => {
+                let params = AnimateParams { time, count: self.instances.len() as u32 };
+                gpu.queue.write_buffer(&self.animate_params_buffer, 0, bytemuck::bytes_of(&params));
+            }
+        }
+
+        // 3. 视锥剔除
+        let cull_params = CullParams {
+            planes: frustum_planes(view_proj),
+            radius: self.mesh_radius,
+            count: self.instances.len() as u32,
+            enabled: self.culling as u32,
+            _pad: 0,
+        };
+        gpu.queue.write_buffer(&self.cull_params_buffer, 0, bytemuck::bytes_of(&cull_params));
+        // the culling pass counts the instances up from 0
+        let draw_args = DrawIndirectArgs {
+            vertex_count: self.num_vertices,
+            instance_count: 0,
+            first_vertex: 0,
+            first_instance: 0,
+        };
+        gpu.queue.write_buffer(&self.draw_args_buffer, 0, draw_args.as_bytes());
+        self.poll_visible_count(&gpu.device);
+
+        if self.show_overlay {
+            let lines = [
+                format!("visible {} / {}", self.visible_count_text(), self.instances.len()),
+                format!("culling {}", if self.culling { "on" } else { "off" }),
+                format!("animation {}", self.animation.name()),
+                format!("shading {}", self.shading.name()),
+                format!("lights {} ({})", self.light_rig_name(), self.lights.len()),
+                format!("shadows {}", self.shadow_text()),
+            ];
+            let width = lines.iter().map(String::len).max().unwrap_or_default() as u32 + 2;
+            self.overlay.fill(0, 0, width, lines.len() as u32 + 2, OVERLAY_BACKGROUND);
+            for (row, line) in lines.iter().enumerate() {
+                self.overlay.text(1, row as u32 + 1, line, OVERLAY_TEXT);
+            }
+        }
+        self.overlay.prepare(&gpu.queue, self.size);
+    }
+
+    fn render(&mut self, _gpu: &GpuContext, frame: &mut Frame) {
+        if self.animation == Animation::Compute {
+            let mut pass = frame.encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Animate Pass"),
+                timestamp_writes: frame.profiler.compute_pass("Animate"),
+            });
+            pass.set_pipeline(&self.animate_pipeline);
+            pass.set_bind_group(0, &self.animate_bind_group, &[]);
+            pass.dispatch_workgroups((self.instances.len() as u32).div_ceil(WORKGROUP_SIZE), 1, 1);
+        }
+        if self.shadow_light.is_some() {
+            // every instance, as those out of view can still cast shadows into it
+            let mut pass = frame.encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Shadow Pass"),
+                color_attachments: &[],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &self.shadow_view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                timestamp_writes: frame.profiler.render_pass("Shadow"),
+                ..Default::default()
+            });
+            pass.set_pipeline(&self.shadow_pipeline);
+            pass.set_bind_group(0, &self.bind_group, &[]);
+            pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+            pass.draw(0..self.num_vertices, 0..self.instances.len() as u32);
+        }
+        {
+            let mut pass = frame.encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Cull Pass"),
+                timestamp_writes: frame.profiler.compute_pass("Cull"),
+            });
+            pass.set_pipeline(&self.cull_pipeline);
+            pass.set_bind_group(0, &self.cull_bind_group, &[]);
+            pass.dispatch_workgroups((self.instances.len() as u32).div_ceil(WORKGROUP_SIZE), 1, 1);
+        }
+        {
+            let mut render_pass = frame.encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &self.msaa_view,
+                    depth_slice: None,
+                    resolve_target: Some(frame.view),
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color { r: 1.0, g: 0.4, b: 0.0, a: 1.0 }),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &self.depth_view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                timestamp_writes: frame.profiler.render_pass("Scene"),
+                ..Default::default()
+            });
+
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_bind_group(0, &self.bind_group, &[]);
+            render_pass.set_bind_group(1, &self.shadow_bind_group, &[]);
+            render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+            render_pass.draw_indirect(&self.draw_args_buffer, 0);
+        }
+        if self.count_readback.is_free() {
+            let offset = std::mem::offset_of!(DrawIndirectArgs, instance_count) as u64;
+            frame.encoder.copy_buffer_to_buffer(&self.draw_args_buffer, offset, &self.count_readback_buffer, 0, Some(4));
+            self.count_readback.set_copied();
+        }
+        self.overlay.encode_pass(frame.encoder, frame.view, frame.profiler);
+    }
+
+    /// G switches between animating the instances on the CPU and in a compute pass, C turns
+    /// culling on and off, O shows the visible instance count, M switches between Blinn-Phong
+    /// and PBR and L cycles through the light rigs. S turns shadows on and off, [ and ] halve
+    /// and double the shadow bias and P cycles the PCF radius from 0 to 3.
+    fn input(&mut self, _gpu: &GpuContext, event: &InputEvent) -> bool {
+        match event {
+            InputEvent::KeyPressed(Key::Character('c')) => {
+                self.culling = !self.culling;
+                true
+            }
+            InputEvent::KeyPressed(Key::Character('o')) => {
+                self.show_overlay = !self.show_overlay;
+                true
+            }
+            InputEvent::KeyPressed(Key::Character('m')) => {
+                self.shading = match self.shading {
+                    Shading::BlinnPhong => Shading::Pbr,
+                    Shading::Pbr => Shading::BlinnPhong,
+                };
+                true
+            }
+            InputEvent::KeyPressed(Key::Character('l')) => {
+                let next = match self.light_rig {
+                    Some(rig) => rig.next(),
+                    None => LightRig::Original,
+                };
+                self.set_light_rig(next);
+                true
+            }
+            InputEvent::KeyPressed(Key::Character('s')) => {
+                self.shadow.enabled = !self.shadow.enabled;
+                true
+            }
+            InputEvent::KeyPressed(Key::Character('[')) => {
+                self.shadow.bias /= 2.0;
+                true
+            }
+            InputEvent::KeyPressed(Key::Character(']')) => {
+                self.shadow.bias *= 2.0;
+                true
+            }
+            InputEvent::KeyPressed(Key::Character('p')) => {
+                self.shadow.pcf_radius = (self.shadow.pcf_radius + 1) % 4;
+                true
+            }
+            InputEvent::KeyPressed(Key::Character('g')) => {
+                self.animation = match self.animation {
+                    Animation::Cpu => Animation::Compute,
+                    Animation::Compute => Animation::Cpu,
+                };
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn resize(&mut self, gpu: &GpuContext, size: (u32, u32)) {
+        self.size = size;
+        let (mv, dv) = Self::create_textures(&gpu.device, self.format, size);
+        self.msaa_view = mv;
+        self.depth_view = dv;
+    }
+
+    fn status(&self) -> Option<String> {
+        Some(format!(
+            "{} of {} instances visible, animation: {}, culling {}, shading: {}, lights: {}, shadows {}",
+            self.visible_count_text(),
+            self.instances.len(),
+            self.animation.name(),
+            if self.culling { "on" } else { "off" },
+            self.shading.name(),
+            self.light_rig_name(),
+            self.shadow_text(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Signed distance of `point` from the plane, positive inside the frustum.
+    fn distance(plane: [f32; 4], point: Vec3) -> f32 {
+        Vec3::from_slice(&plane).dot(point) + plane[3]
+    }
+
+    #[test]
+    fn frustum_planes_of_perspective() {
+        let view = Mat4::look_at_lh(Vec3::new(0.0, 0.0, -10.0), Vec3::ZERO, Vec3::Y);
+        let projection = Mat4::perspective_lh(90f32.to_radians(), 1.0, 1.0, 50.0);
+        let planes = frustum_planes(projection * view);
+
+        for plane in planes {
+            let normal = Vec3::from_slice(&plane);
+            assert!((normal.length() - 1.0).abs() < 1e-5);
+            assert!(distance(plane, Vec3::ZERO) > 0.0);
+        }
+        // left, right, bottom, top, near, far
+        let outside = [
+            Vec3::new(-20.0, 0.0, 0.0),
+            Vec3::new(20.0, 0.0, 0.0),
+            Vec3::new(0.0, -20.0, 0.0),
+            Vec3::new(0.0, 20.0, 0.0),
+            Vec3::new(0.0, 0.0, -9.5),
+            Vec3::new(0.0, 0.0, 41.0),
+        ];
+        for (plane, point) in planes.into_iter().zip(outside) {
+            assert!(distance(plane, point) < 0.0, "{:?} is inside", point);
+        }
+        // the near plane is 1 away from the eye; normalization makes that the distance
+        assert!((distance(planes[4], Vec3::new(0.0, 0.0, -10.0)) + 1.0).abs() < 1e-4);
+    }
+}